@@ -1,13 +1,26 @@
-use js_sys::Math;
 use wasm_bindgen::__rt::core::f64::consts::PI;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 
 use crate::color::Color;
-use crate::flower::Flower;
-use crate::sdf::{find_closest_point, raycast, sdf_sphere};
-use crate::threed::{Ray, Vec3};
-use crate::utils::{current_time_millis, lerpf, gaussian_blur};
+use crate::export::{max_frames, Export, FrameState, Sequence};
+use crate::flower::{Component, CompoundLeaf, Flower, Grip, LeafArrangement, Shape};
+use crate::history::History;
+use crate::keyframe::{Animation, Easing, Keyframe, Param, Pose};
+use crate::keymap::{Chord, Command, Keymap};
+use crate::lsystem::{LSystem, LSystemError};
+use crate::palette::Palette;
+use crate::phyllotaxis::Phyllotaxis;
+use crate::physics::{Physics, Wind};
+use crate::pointer::{Pointer, PointerKind, Pointers, ALT_KEY, MIDDLE_BUTTON, PRIMARY_BUTTON, SECONDARY_BUTTON, SHIFT_KEY};
+use crate::render::{RenderMode, Renderer};
+use crate::sepal::Sepals;
+use crate::snap::{angle_step, Snap};
+use crate::thorn::Thorns;
+use crate::threed::{Aabb, Vec3};
+use crate::timeline::Timeline;
+use crate::utils::{current_time_millis, lerpf};
+use crate::viewport::{Rect, RenderRegion, View};
 
 #[wasm_bindgen]
 extern "C" {
@@ -17,17 +30,45 @@ extern "C" {
 
 const MOUSE_RADIUS: f64 = 10.;
 
+// more leaves than this just crowd each other out, and slow every frame down.
+const MAX_ARRANGED_LEAVES: u32 = 64;
+
+// likewise thorns along the stem, and sepals under each bloom.
+const MAX_THORNS: u32 = 100;
+const MAX_SEPALS: u32 = 12;
+
+// fingers are much less precise than a mouse or pen, so they grab handles from further away.
+const TOUCH_RADIUS: f64 = 24.;
+
+// a select box smaller than this across, in screen pixels, was a click.
+const CLICK_DISTANCE: f64 = 3.;
+
+// how big the grips on the branch or leaf being edited are, in screen pixels.
+const GRIP_SIZE: f64 = 8.;
+
+// how far toward the viewer, in the scene, a handle is drawn twice its size. handles further
+// away are drawn smaller, down to half size.
+const DEPTH_SCALE: f64 = 200.;
+
+// how much one pixel of mouse wheel scrolling zooms, exponentially.
+const WHEEL_ZOOM: f64 = 0.002;
+
+// how long the plant takes to grow from a seedling, when played.
+const GROWTH_DURATION: f64 = 8000.;
+
 #[wasm_bindgen]
 struct Handle {
     pos: Vec3,
     hovered: bool,
+    selected: bool,
 }
 
 impl Handle {
-    pub fn new(x: f64, y: f64) -> Self {
+    pub fn new(pos: Vec3) -> Self {
         Self {
-            pos: Vec3::new(x, y, 0.),
+            pos,
             hovered: false,
+            selected: false,
         }
     }
 
@@ -35,24 +76,92 @@ impl Handle {
         self.hovered = hovered;
     }
 
-    pub fn contains_mouse(&self, mouse: &Vec3) -> bool {
-        self.pos.dist2(mouse) < MOUSE_RADIUS * MOUSE_RADIUS
+    // handles are grabbed wherever they are on screen, whatever their depth.
+    pub fn contains_mouse(&self, mouse: &Vec3, radius: f64) -> bool {
+        (self.pos.x - mouse.x).hypot(self.pos.y - mouse.y) < radius
+    }
+
+    // how much bigger it's drawn for being closer to the viewer, as a cue to its depth.
+    fn depth_scale(&self) -> f64 {
+        (1. - self.pos.z / DEPTH_SCALE).clamp(0.5, 2.)
+    }
+}
+
+// a box dragged out on the canvas to select the handles inside it.
+struct SelectBox {
+    start: (f64, f64),
+    end: (f64, f64),
+    // whether it adds to the selection, rather than replacing it.
+    additive: bool,
+}
+
+impl SelectBox {
+    fn rect(&self) -> Rect {
+        Rect::from_corners(self.start.0, self.start.1, self.end.0, self.end.1)
     }
 }
 
+// what undo puts back: where the handles were, and how the branches and leaves were shaped.
+struct Snapshot {
+    positions: Vec<Vec3>,
+    shape: Shape,
+}
+
 #[wasm_bindgen]
 pub struct Canvas {
     canvas: web_sys::HtmlCanvasElement,
     g: web_sys::CanvasRenderingContext2d,
     width: f64,
     height: f64,
+    // where the primary pointer is, in the scene.
     mouse: Vec3,
+    // how far from a handle, in the scene, the primary pointer grabs it.
+    grab_radius: f64,
+    // how hard the primary pointer is pressing, in [0, 1].
+    pressure: f64,
+    pointers: Pointers,
+    // where the pointer dragging the view around was last, on the canvas.
+    panning: Option<(f64, f64)>,
     handles: Vec<Handle>,
     is_setup: bool,
     dragging_handle: Option<usize>,
+    // where the handles were when the drag started. every selected handle moves with the one
+    // dragged.
+    drag_start: Vec<Vec3>,
+    // where the pointer pressed to start the drag, in the scene, and whether it's dragging the
+    // handles in depth rather than across the screen.
+    drag_origin: Vec3,
+    dragging_depth: bool,
+    // the branch or leaf clicked on to edit, whose grips are shown, and the grip being dragged.
+    component: Option<Component>,
+    dragging_grip: Option<Grip>,
+    // the plant as it was when the drag started, recorded once the drag changes it.
+    before_drag: Option<Snapshot>,
+    selecting: Option<SelectBox>,
+    snap: Snap,
+    // the plant as it was before each edit, to undo them.
+    history: History<Snapshot>,
+    keymap: Keymap,
     is_click_frame: bool,
     user_event: bool,
-    render_pixel: usize,
+    region: RenderRegion,
+    region_changed: bool,
+    renderer: Renderer,
+    flower: Flower,
+    // what the current plant was grown from; None for the default, hand-designed plant.
+    seed: Option<u32>,
+    // bounds of each part of the plant as of the last edit, to find what an edit touched.
+    plant_bounds: Option<Vec<Aabb>>,
+    // how far the plant has grown, from seedling (0) to as designed (1).
+    growth: Timeline,
+    export: Option<Export>,
+    // where the handles were before an export started moving them, to put them back after.
+    handles_before_export: Vec<Vec3>,
+    // keyframed handles and parameters, played back each frame.
+    animation: Animation,
+    // bends the stem and branches under gravity and wind while `swaying`.
+    physics: Physics,
+    swaying: bool,
 }
 
 #[wasm_bindgen]
@@ -64,6 +173,7 @@ impl Canvas {
             .unwrap()
             .dyn_into::<web_sys::CanvasRenderingContext2d>()
             .unwrap();
+        let renderer = Renderer::new(&canvas.owner_document().unwrap());
 
         Self {
             canvas,
@@ -71,177 +181,619 @@ impl Canvas {
             width: 0.,
             height: 0.,
             mouse: Vec3::zero(),
+            grab_radius: MOUSE_RADIUS,
+            pressure: 0.,
+            pointers: Pointers::new(),
+            panning: None,
             handles: vec![],
             is_setup: false,
             dragging_handle: None,
+            drag_start: vec![],
+            drag_origin: Vec3::zero(),
+            dragging_depth: false,
+            component: None,
+            dragging_grip: None,
+            before_drag: None,
+            selecting: None,
+            snap: Snap::none(),
+            history: History::new(),
+            keymap: Keymap::default(),
             is_click_frame: false,
             user_event: false,
-            render_pixel: 0,
+            region: RenderRegion::FullCanvas,
+            region_changed: true,
+            renderer,
+            flower: Flower::new(),
+            seed: None,
+            plant_bounds: None,
+            growth: Timeline::new(GROWTH_DURATION),
+            export: None,
+            handles_before_export: vec![],
+            animation: Animation::new(),
+            physics: Physics::new(),
+            swaying: false,
         }
     }
 
     pub fn setup(&mut self) {
-        self.handles.push(Handle::new(354., 591.));
-        self.handles.push(Handle::new(395., 410.));
-        self.handles.push(Handle::new(259., 399.));
-        self.handles.push(Handle::new(310., 211.));
+        self.reset_handles();
 
         self.is_setup = true;
     }
 
+    fn reset_handles(&mut self) {
+        self.handles = self.flower.controls().iter()
+            .map(|pt| Handle::new(pt.clone()))
+            .collect();
+        self.dragging_handle = None;
+        self.dragging_grip = None;
+        self.component = None;
+        // keyframes and undo history are keyed by handle, so they don't carry over to a
+        // different plant's handles.
+        self.animation.clear();
+        self.history.clear();
+    }
+
+    fn positions(&self) -> Vec<Vec3> {
+        self.handles.iter().map(|h| h.pos.clone()).collect()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            positions: self.positions(),
+            shape: self.flower.shape(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        for (handle, pos) in self.handles.iter_mut().zip(snapshot.positions) {
+            handle.pos = pos;
+        }
+        self.flower.set_shape(snapshot.shape);
+        self.user_event = true;
+    }
+
+    // the drag in progress changed the plant, so it can be undone.
+    fn edited(&mut self) {
+        if let Some(before) = self.before_drag.take() {
+            self.history.record(before);
+        }
+    }
+
     pub fn update(&mut self) {
         self.width = self.canvas.width() as f64;
         self.height = self.canvas.height() as f64;
+        if self.renderer.resize(self.width, self.height) {
+            self.region_changed = true;
+            self.user_event = true;
+        }
 
         if !self.is_setup {
             self.setup();
             self.user_event = true;
         }
 
-        if self.user_event {
-            self.g.clear_rect(0., 0., self.width, self.height);
-            self.set_fill_color(&Color::white());
-            self.g.fill_rect(0., 0., self.width, self.height);
+        self.step_export();
 
-            //self.render_handle_bezier();
+        if let Some(growth) = self.growth.tick(current_time_millis()) {
+            self.flower.set_growth(growth);
+            self.user_event = true;
+        }
 
-            self.render_control_lines();
+        if let Some(pose) = self.animation.tick(current_time_millis()) {
+            self.apply_pose(pose);
+        }
 
-            for i in 0..self.handles.len() {
-                self.render_handle(&self.handles[i]);
+        let mut controls: Vec<Vec3> = self.handles.iter()
+            .map({ |h| h.pos.clone() })
+            .collect();
+        // the handles are the plant at rest; it's drawn where gravity and wind push it. an export
+        // holds it still so every frame is posed exactly. once it's settled, it's left where it
+        // is.
+        if self.swaying && self.export.is_none() {
+            let bent = self.physics.step(current_time_millis(), &controls, &self.flower.branch_rests());
+            if bent.moving {
+                self.flower.bend_branches(&bent.bends);
+                self.user_event = true;
             }
+            controls = if bent.moving { bent.controls } else { self.flower.controls().clone() };
+        }
+        // rebuilding the plant is expensive, so it's only done when the stem has moved.
+        if controls != *self.flower.controls() {
+            self.flower.update_controls(&controls);
+        }
 
-            self.render_pixel = 0;
+        if self.user_event {
+            let changed = self.invalidate_changed_parts();
+            if changed || self.region_changed {
+                let rect = self.renderer.resolve_rect(&self.region, &self.flower);
+                self.renderer.set_rect(rect);
+                self.region_changed = false;
+            }
         }
 
-        if !self.user_event && self.render_pixel == 0 {
+        if !self.user_event && self.renderer.is_idle() {
             return;
         }
         self.user_event = false;
 
-        let start_time_millis = current_time_millis() as u64;
-        let deadline = start_time_millis + 10u64; // 10ms in the future
+        let deadline = current_time_millis() + 10.; // 10ms in the future
+        self.renderer.render(&self.flower, deadline);
 
-        let flower = {
-            let mut flower = Flower::new();
-            flower.update_controls(&self.handles.iter()
-                .map({ |h| h.pos.clone() })
-                .collect());
-            flower
-        };
+        self.g.clear_rect(0., 0., self.width, self.height);
+        self.renderer.blit(&self.g);
 
-        let length = self.width as usize * self.height as usize;
-        while (current_time_millis() as u64) < deadline {
-            let y = self.height - 1. - (self.render_pixel / (self.width as usize)) as f64;
-            let x = (self.render_pixel % (self.width as usize)) as f64;
-            if x > self.width * 0.99 {
-                self.set_fill_color(&Color::black());
-                self.g.fill_rect(x, y, 1., 1.);
-            } else if x > self.width / 2. {
-                // noop hack
-            } else {
-                // render outline
-                // TODO anti-alias.
-                if let Some(hit) = raycast(
-                    &Ray::new(Vec3::new(x, y, -10.), Vec3::forward()),
-                    100.,
-                    &|s| flower.distance(s) - 2.,
-                ) {
-                    self.set_fill_color(&Color::black());
-                    self.g.fill_rect(x, y, 1., 1.);
-                }
+        //self.render_handle_bezier();
 
-                // render shaded rose
-                self.render_rose(&flower, x, y);
-            }
-            self.render_pixel = {
-                let next = self.render_pixel + 1;
-                if next >= length {
-                    next - length
-                } else {
-                    next
-                }
-            };
+        self.render_control_lines();
+
+        for i in 0..self.handles.len() {
+            // the handle being dragged swells as it's pressed harder.
+            let scale = if self.dragging_handle == Some(i) { lerpf(0.8, 1.3, self.pressure) } else { 1. };
+            self.render_handle(&self.handles[i], scale);
+        }
+
+        if let Some(component) = self.component {
+            self.render_grips(component);
+        }
+
+        if let Some(select) = &self.selecting {
+            let rect = select.rect();
+            self.g.set_line_width(1.);
+            self.set_stroke_color(&Color::black());
+            self.g.stroke_rect(rect.x, rect.y, rect.width, rect.height);
         }
 
         self.is_click_frame = false;
     }
 
-    fn render_rose(&self, flower: &Flower, x: f64, y: f64) {
-        let light_pos = Vec3::new(
-            self.width * 0.75,
-            self.height / 2.,
-            -self.width * 0.25,
-        );
+    // queues re-rendering of just the screen area covered by the parts of the plant that moved,
+    // both where they were and where they are now.
+    fn invalidate_changed_parts(&mut self) -> bool {
+        let bounds = self.flower.bounds();
+        let mut changed = false;
+        match &self.plant_bounds {
+            Some(old) if old.len() == bounds.len() => {
+                for (old, new) in old.iter().zip(bounds.iter()) {
+                    if old != new {
+                        self.renderer.invalidate_bounds(old);
+                        self.renderer.invalidate_bounds(new);
+                        changed = true;
+                    }
+                }
+            }
+            _ => {
+                self.renderer.invalidate_all();
+                changed = true;
+            }
+        }
+        self.renderer.fit_depth(&bounds);
+        self.plant_bounds = Some(bounds);
+        changed
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+
+    // replaces the plant with a randomly grown one; the same seed always grows the same plant.
+    pub fn set_seed(&mut self, seed: u32) {
+        let (width, height) = (self.canvas.width() as f64, self.canvas.height() as f64);
+        self.replace_flower(Flower::from_seed(seed as u64, width, height));
+        self.seed = Some(seed);
+    }
+
+    // replaces the plant with one grown from l-system source like
+    //   axiom=A; angle=25; step=20; A=F[+A][-L]FK
+    // (see `LSystem`), or the built-in rose bush if the source is empty.
+    pub fn set_lsystem(&mut self, source: &str, iterations: u32) -> Result<(), JsValue> {
+        let to_js = |e: LSystemError| JsValue::from_str(&e.to_string());
+        let lsystem = if source.trim().is_empty() {
+            LSystem::rose_bush()
+        } else {
+            LSystem::parse(source).map_err(to_js)?
+        };
+        let (width, height) = (self.canvas.width() as f64, self.canvas.height() as f64);
+        let flower = Flower::grow(&lsystem, iterations as usize, width, height).map_err(to_js)?;
+        self.replace_flower(flower);
+        self.seed = None;
+        Ok(())
+    }
+
+    pub fn phyllotaxis(&self) -> Option<String> {
+        self.flower.arrangement().map(|a| a.pattern.name().to_string())
+    }
 
-        let mut result_color = Color::black();
-        let mut hits = 0;
+    // arranges `leaves` leaves around the stem, "alternate", "opposite" or "spiral", in place of
+    // the plant's current branches.
+    pub fn set_phyllotaxis(&mut self, pattern: &str, leaves: u32) -> Result<(), JsValue> {
+        let pattern = Phyllotaxis::from_name(pattern)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown phyllotaxis '{}'", pattern)))?;
+        if leaves > MAX_ARRANGED_LEAVES {
+            return Err(JsValue::from_str(&format!("at most {} leaves can be arranged", MAX_ARRANGED_LEAVES)));
+        }
+        let arrangement = match self.flower.arrangement() {
+            Some(current) => LeafArrangement { pattern, leaves: leaves as usize, ..current.clone() },
+            None => LeafArrangement::new(pattern, leaves as usize),
+        };
+        self.flower.set_arrangement(arrangement);
+        self.user_event = true;
+        Ok(())
+    }
 
-        // blur sample step
-        let eps = 0.5;
+    // gives every leaf `pairs` pairs of leaflets plus one at the tip, like a rose's; 0 makes
+    // them simple again.
+    pub fn set_compound_leaves(&mut self, pairs: u32) {
+        let compound = match (pairs, self.flower.compound_leaves()) {
+            (0, _) => None,
+            (pairs, Some(current)) => Some(CompoundLeaf { pairs: pairs as usize, ..current.clone() }),
+            (pairs, None) => Some(CompoundLeaf::new(pairs as usize)),
+        };
+        self.flower.set_compound_leaves(compound);
+        self.user_event = true;
+    }
 
-        // blur controls
-        let sigma = 1.;
+    // scatters `count` thorns `size` long along the stem; `curvature` is how far their tips
+    // hook back down it, from 0 (straight) to 1.
+    pub fn set_thorns(&mut self, count: u32, size: f64, curvature: f64) -> Result<(), JsValue> {
+        check_shape("thorn", size, curvature)?;
+        self.flower.set_thorns(Thorns {
+            count: count.min(MAX_THORNS) as usize,
+            size,
+            curvature: curvature.clamp(0., 1.),
+            ..self.flower.thorns().clone()
+        });
+        self.user_event = true;
+        Ok(())
+    }
+
+    // puts `count` sepals `size` long under every bloom; `curvature` is how far they curl back
+    // toward the stem, from 0 to 1.
+    pub fn set_sepals(&mut self, count: u32, size: f64, curvature: f64) -> Result<(), JsValue> {
+        check_shape("sepal", size, curvature)?;
+        self.flower.set_sepals(Sepals::new(count.min(MAX_SEPALS) as usize, size, curvature.clamp(0., 1.)));
+        self.user_event = true;
+        Ok(())
+    }
 
-        // NB: set to 3 for nice blurring, 1 for speed
-        let window_size = 1;
+    pub fn growth(&self) -> f64 {
+        self.growth.position()
+    }
 
-        let mut deltas = vec![];
+    pub fn is_growing(&self) -> bool {
+        self.growth.is_playing()
+    }
 
-        for ix in 0..window_size {
-            let dx = (ix - window_size / 2) as f64;
-            for iy in 0..window_size {
-                let dy = (iy - window_size / 2) as f64;
-                deltas.push((dx, dy));
-            }
+    // plays the plant growing, from where the timeline is, or from a seedling if it's fully
+    // grown.
+    pub fn play_growth(&mut self) {
+        self.growth.play(current_time_millis());
+        self.flower.set_growth(self.growth.position());
+        self.user_event = true;
+    }
+
+    pub fn pause_growth(&mut self) {
+        self.growth.pause();
+    }
+
+    // shows the plant `growth` of the way from seedling (0) to fully grown (1).
+    pub fn scrub_growth(&mut self, growth: f64) {
+        self.growth.scrub(growth);
+        self.flower.set_growth(self.growth.position());
+        self.user_event = true;
+    }
+
+    pub fn set_growth_seconds(&mut self, seconds: f64) {
+        self.growth.set_duration(seconds * 1000.);
+    }
+
+    // keys handle `handle` to x, y, at its current depth, at `seconds` into the animation.
+    // `easing` is how it moves on to the handle's next keyframe: "linear", "ease", "ease-in",
+    // "ease-out", "ease-in-out", "cubic-bezier(x1, y1, x2, y2)", "spring" or
+    // "spring(stiffness, damping)".
+    pub fn add_handle_keyframe(&mut self, handle: u32, seconds: f64, x: f64, y: f64, easing: &str) -> Result<(), JsValue> {
+        if handle as usize >= self.handles.len() {
+            return Err(JsValue::from_str(&format!("no handle {}", handle)));
         }
+        check_keyframe(seconds, &[x, y])?;
+        let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let z = self.handles[handle as usize].pos.z;
+        self.animation.add_handle_keyframe(handle as usize, Keyframe {
+            time: seconds * 1000.,
+            value: Vec3::new(x, y, z),
+            easing,
+        });
+        Ok(())
+    }
 
-        let total_alpha = deltas.iter()
-            .map({ |(dx, dy)| gaussian_blur(sigma, *dx, *dy) })
-            .fold(0., |a, b| { a + b });
-
-        for (dx, dy) in deltas {
-            let g = gaussian_blur(sigma, dx, dy) / total_alpha;
-
-            let pt = Vec3::new(
-                x + dx as f64 * eps,
-                y + dy as f64 * eps,
-                0.,
-            );
-
-            if let Some(hit) = raycast(
-                &Ray::new(Vec3::new(pt.x, pt.y, -10.), Vec3::forward()),
-                100.,
-                &|s| flower.distance(s),
-            ) {
-                let light_dir = (&light_pos - &hit.point).unit();
-                let diffuse = hit.normal.dot(&light_dir).max(0.);
-                let ambient = 0.2;
-                let albedo = ambient + diffuse;
-                let c = Color::white().scale(albedo);
-
-                result_color = &result_color + &(&c * g);
-                hits += 1;
-            } else {
-                result_color = &result_color + &Color::white().scale(g);
+    // keys "growth" (0 to 1) or "turn" (radians) to `value` at `seconds` into the animation,
+    // easing on to the next keyframe as for handles.
+    pub fn add_param_keyframe(&mut self, name: &str, seconds: f64, value: f64, easing: &str) -> Result<(), JsValue> {
+        let param = Param::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown parameter '{}'", name)))?;
+        check_keyframe(seconds, &[value])?;
+        let easing = Easing::parse(easing).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.animation.add_param_keyframe(param, Keyframe { time: seconds * 1000., value, easing });
+        Ok(())
+    }
+
+    pub fn clear_keyframes(&mut self) {
+        self.animation.clear();
+    }
+
+    // plays the keyframes from the start, over and over if `looping`, so a plant can sway
+    // back and forth for as long as it's shown.
+    pub fn play_keyframes(&mut self, looping: bool) {
+        self.animation.play(current_time_millis(), looping);
+        self.user_event = true;
+    }
+
+    // stops where it is, leaving the handles there.
+    pub fn stop_keyframes(&mut self) {
+        self.animation.stop();
+    }
+
+    pub fn is_playing_keyframes(&self) -> bool {
+        self.animation.is_playing()
+    }
+
+    pub fn is_swaying(&self) -> bool {
+        self.swaying
+    }
+
+    // lets the stem and branches bend under gravity and wind, springing back toward the
+    // handles. turning it off straightens them back out.
+    pub fn set_swaying(&mut self, swaying: bool) {
+        self.swaying = swaying;
+        self.physics.reset();
+        if !swaying {
+            self.flower.bend_branches(&[]);
+        }
+        self.user_event = true;
+    }
+
+    // how hard gravity pulls down, in pixels per second squared.
+    pub fn set_gravity(&mut self, gravity: f64) {
+        self.physics.gravity = gravity;
+    }
+
+    // wind blowing `strength` pixels per second squared toward `angle` radians around the
+    // vertical (0 is to the right, a quarter turn is away from the viewer), with gusts varying
+    // it by `gustiness` of that.
+    pub fn set_wind(&mut self, strength: f64, angle: f64, gustiness: f64) {
+        self.physics.wind = Wind {
+            direction: Vec3::new(angle.cos(), 0., angle.sin()),
+            strength,
+            gustiness: gustiness.max(0.),
+        };
+    }
+
+    // how quickly swaying dies down, per second.
+    pub fn set_sway_damping(&mut self, damping: f64) {
+        self.physics.damping = damping.max(0.);
+    }
+
+    // renders `frames` frames of the plant growing from a seedling, for `export_apng` and
+    // `export_frame_png`.
+    pub fn export_growth(&mut self, frames: u32, fps: f64) -> Result<(), JsValue> {
+        self.start_export(Sequence::Growth, frames, fps)
+    }
+
+    // renders `frames` frames of one full turn of the plant around its stem.
+    pub fn export_turntable(&mut self, frames: u32, fps: f64) -> Result<(), JsValue> {
+        self.start_export(Sequence::Turntable, frames, fps)
+    }
+
+    // renders `frames` frames of the handles moving through keyframes, given as x, y for each
+    // handle in order, one keyframe after another. handles stay at their current depths.
+    pub fn export_handle_keyframes(&mut self, keyframes: &[f64], frames: u32, fps: f64) -> Result<(), JsValue> {
+        let per_keyframe = self.handles.len() * 2;
+        let mismatched = || JsValue::from_str(&format!(
+            "expected keyframes of {} numbers (x, y for each handle)", per_keyframe));
+        if per_keyframe == 0 {
+            return Err(mismatched());
+        }
+        let chunks = keyframes.chunks_exact(per_keyframe);
+        if !chunks.remainder().is_empty() {
+            return Err(mismatched());
+        }
+        let keyframes = chunks
+            .map(|k| k.chunks(2).zip(&self.handles).map(|(p, h)| Vec3::new(p[0], p[1], h.pos.z)).collect())
+            .collect();
+        self.start_export(Sequence::Handles(keyframes), frames, fps)
+    }
+
+    // the most frames an export at the canvas's current size can have.
+    pub fn max_export_frames(&self) -> u32 {
+        max_frames(self.width as u32, self.height as u32).min(u32::MAX as usize) as u32
+    }
+
+    pub fn is_exporting(&self) -> bool {
+        self.export.as_ref().is_some_and(|e| !e.is_finished())
+    }
+
+    // how much of the current export has been rendered, in [0, 1].
+    pub fn export_progress(&self) -> f64 {
+        self.export.as_ref().map_or(0., |e| e.progress())
+    }
+
+    pub fn export_frame_count(&self) -> u32 {
+        self.export.as_ref().map_or(0, |e| e.frame_count() as u32)
+    }
+
+    // a name for each frame, numbered so they sort in order.
+    pub fn export_frame_name(&self, frame: u32) -> Option<String> {
+        self.export.as_ref().map(|e| e.frame_name(frame as usize))
+    }
+
+    pub fn export_frame_png(&self, frame: u32) -> Result<Vec<u8>, JsValue> {
+        self.finished_export()?
+            .png(frame as usize)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    // every frame as one looping animated png.
+    pub fn export_apng(&self) -> Result<Vec<u8>, JsValue> {
+        self.finished_export()?
+            .apng()
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn cancel_export(&mut self) {
+        if self.is_exporting() {
+            self.finish_export();
+        }
+        self.export = None;
+    }
+
+    fn start_export(&mut self, sequence: Sequence, frames: u32, fps: f64) -> Result<(), JsValue> {
+        if self.is_exporting() {
+            return Err(JsValue::from_str("already exporting"));
+        }
+        let export = Export::new(sequence, frames as usize, fps, self.width as u32, self.height as u32)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.growth.pause();
+        self.animation.stop();
+        self.handles_before_export = self.positions();
+        self.export = Some(export);
+        self.user_event = true;
+        Ok(())
+    }
+
+    fn finished_export(&self) -> Result<&Export, JsValue> {
+        self.export.as_ref().ok_or_else(|| JsValue::from_str("nothing has been exported"))
+    }
+
+    // once the last frame is rendered, sets the plant up for the next one.
+    fn step_export(&mut self) {
+        let export = match &mut self.export {
+            Some(export) if !export.is_finished() => export,
+            _ => return,
+        };
+        if export.is_rendering() {
+            if !self.renderer.is_idle() {
+                return;
+            }
+            export.add_frame(self.renderer.pixels());
+        }
+        match export.next_frame() {
+            Some(FrameState::Growth(growth)) => self.flower.set_growth(growth),
+            Some(FrameState::Turn(angle)) => self.flower.set_turn(angle),
+            Some(FrameState::Handles(positions)) => {
+                for (handle, pos) in self.handles.iter_mut().zip(positions) {
+                    handle.pos = pos;
+                }
             }
+            None => self.finish_export(),
         }
+        self.user_event = true;
+    }
 
-        if hits == 0 {
-            return;
+    // puts the plant back how it was before the export.
+    fn finish_export(&mut self) {
+        self.flower.set_growth(self.growth.position());
+        self.flower.set_turn(0.);
+        for (handle, pos) in self.handles.iter_mut().zip(self.handles_before_export.drain(..)) {
+            handle.pos = pos;
         }
+        self.user_event = true;
+    }
 
-        //result_color = &result_color + &Color::white().scale(1.0 - total_alpha);
+    fn apply_pose(&mut self, pose: Pose) {
+        for (handle, pos) in self.handles.iter_mut().zip(pose.handles) {
+            if let Some(pos) = pos {
+                handle.pos = pos;
+            }
+        }
+        for (param, value) in pose.params {
+            match param {
+                Param::Growth => self.flower.set_growth(value.clamp(0., 1.)),
+                Param::Turn => self.flower.set_turn(value),
+            }
+        }
+        self.user_event = true;
+    }
 
-        self.set_fill_color(&result_color);
-        self.g.fill_rect(x, y, 1., 1.);
+    fn replace_flower(&mut self, mut flower: Flower) {
+        flower.set_growth(self.growth.position());
+        self.flower = flower;
+        self.physics.reset();
+        self.reset_handles();
+        self.is_setup = true;
+        // the whole plant is different, not just some parts of it.
+        self.plant_bounds = None;
+        self.user_event = true;
+    }
+
+    // accepts any css color; bad input is reported back to js rather than panicking.
+    pub fn set_background_color(&mut self, color: &str) -> Result<(), JsValue> {
+        let color = Color::parse(color).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer.set_background(color);
+        self.user_event = true;
+        Ok(())
+    }
+
+    pub fn palette(&self) -> String {
+        self.renderer.palette().name.clone()
+    }
+
+    pub fn set_palette(&mut self, name: &str) -> Result<(), JsValue> {
+        let palette = Palette::named(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown palette '{}'", name)))?;
+        self.renderer.set_palette(palette);
+        self.user_event = true;
+        Ok(())
+    }
+
+    pub fn render_mode(&self) -> String {
+        self.renderer.mode().name().to_string()
+    }
+
+    // "shaded" or "watercolor".
+    pub fn set_render_mode(&mut self, name: &str) -> Result<(), JsValue> {
+        let mode = RenderMode::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown render mode '{}'", name)))?;
+        self.renderer.set_mode(mode);
+        self.user_event = true;
+        Ok(())
+    }
+
+    pub fn set_sdf_cache(&mut self, enabled: bool) {
+        self.renderer.set_sdf_cache(enabled);
+    }
+
+    pub fn set_render_region_full(&mut self) {
+        self.region = RenderRegion::FullCanvas;
+        self.region_changed = true;
+        self.user_event = true;
+    }
+
+    pub fn set_render_region_plant(&mut self) {
+        self.region = RenderRegion::PlantBounds;
+        self.region_changed = true;
+        self.user_event = true;
+    }
+
+    // zooms and pans back to showing the scene at its actual size.
+    pub fn reset_view(&mut self) {
+        self.set_view(View::identity());
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.renderer.view().scale
+    }
+
+    pub fn set_render_region_crop(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.region = RenderRegion::Crop(Rect::new(x, y, width, height));
+        self.region_changed = true;
+        self.user_event = true;
     }
 
     fn render_control_lines(&self) {
         self.g.begin_path();
         self.g.set_line_width(2.);
         for i in 0..self.handles.len() {
-            let pos = &self.handles[i].pos;
+            let pos = self.renderer.view().to_screen(&self.handles[i].pos);
             if i == 0 {
                 self.g.move_to(pos.x, pos.y);
             } else {
@@ -281,7 +833,7 @@ impl Canvas {
         let N = 1000;
         for i in 0..N {
             let s = (i as f64) / (N as f64);
-            let pt = curve(s);
+            let pt = self.renderer.view().to_screen(&curve(s));
             if i == 0 {
                 self.g.move_to(pt.x, pt.y);
             } else {
@@ -290,73 +842,481 @@ impl Canvas {
         }
     }
 
-    fn render_handle(&self, handle: &Handle) {
+    // handles stay the same size on screen however far it's zoomed, but they're bigger the
+    // closer they are to the viewer.
+    fn render_handle(&self, handle: &Handle, scale: f64) {
+        let pos = self.renderer.view().to_screen(&handle.pos);
+        let scale = scale * handle.depth_scale();
+        let fill = if handle.selected { Color::black() } else { Color::white() };
         let rh = |rad: f64| {
             self.g.begin_path();
-            self.set_fill_color(&Color::white());
-            self.circle(&handle.pos, rad);
+            self.set_fill_color(&fill);
+            self.circle(&pos, rad);
             self.g.fill();
             self.g.close_path();
 
             self.g.begin_path();
             self.set_stroke_color(&Color::black());
             self.g.set_line_width(2.);
-            self.circle(&handle.pos, rad);
+            self.circle(&pos, rad);
             self.g.stroke();
             self.g.close_path();
         };
 
         if handle.hovered {
-            rh(MOUSE_RADIUS);
+            rh(MOUSE_RADIUS * scale);
         } else {
-            rh(MOUSE_RADIUS / 2.);
+            rh(MOUSE_RADIUS / 2. * scale);
         }
     }
 
-    pub fn handle_key_down(&mut self, chr: &str) {
-        //log(&format!("keydown: '{}'", chr));
+    // squares on the grips of the branch or leaf being edited, joined up in order from where
+    // it attaches.
+    fn render_grips(&self, component: Component) {
+        let view = self.renderer.view();
+        let grips: Vec<Vec3> = self.flower.grips(component).iter()
+            .map(|(_, pos)| view.to_screen(pos))
+            .collect();
+        self.g.begin_path();
+        self.g.set_line_width(1.);
+        self.set_stroke_color(&Color::black());
+        for (i, pos) in grips.iter().enumerate() {
+            if i == 0 {
+                self.g.move_to(pos.x, pos.y);
+            } else {
+                self.g.line_to(pos.x, pos.y);
+            }
+        }
+        self.g.stroke();
+        self.g.close_path();
+
+        self.g.set_line_width(2.);
+        self.set_fill_color(&Color::white());
+        for pos in &grips {
+            let corner = (pos.x - GRIP_SIZE / 2., pos.y - GRIP_SIZE / 2.);
+            self.g.fill_rect(corner.0, corner.1, GRIP_SIZE, GRIP_SIZE);
+            self.g.stroke_rect(corner.0, corner.1, GRIP_SIZE, GRIP_SIZE);
+        }
+    }
+
+    // runs whatever command the key is bound to, returning whether there was one. keys that
+    // aren't bound are left to the browser.
+    pub fn handle_key_down(&mut self, key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> bool {
+        match self.keymap.command(&Chord::new(key, ctrl, alt, shift, meta)) {
+            Some(command) => {
+                self.run(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // binds a chord like "Ctrl+Shift+Z" to a command: "toggle-render-mode", "reset-view",
+    // "next-handle", "previous-handle", "nudge-left", "nudge-right", "nudge-up", "nudge-down"
+    // (and "-far" versions of them), "undo" or "redo".
+    pub fn bind_key(&mut self, chord: &str, command: &str) -> Result<(), JsValue> {
+        self.keymap.bind(chord, command).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn unbind_key(&mut self, chord: &str) -> Result<(), JsValue> {
+        self.keymap.unbind(chord).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn reset_key_bindings(&mut self) {
+        self.keymap = Keymap::default();
+    }
+
+    // puts the plant back how it was before the last edit, returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo(self.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn run(&mut self, command: Command) {
+        match command {
+            Command::ToggleRenderMode => {
+                let mode = match self.renderer.mode() {
+                    RenderMode::Shaded => RenderMode::Watercolor,
+                    RenderMode::Watercolor => RenderMode::Shaded,
+                };
+                self.renderer.set_mode(mode);
+            }
+            Command::ResetView => self.reset_view(),
+            Command::NextHandle => self.select_next(1),
+            Command::PreviousHandle => self.select_next(self.handles.len().saturating_sub(1)),
+            Command::Nudge(dx, dy) => self.nudge(dx as f64, dy as f64),
+            Command::Undo => {
+                self.undo();
+            }
+            Command::Redo => {
+                self.redo();
+            }
+        }
+        self.user_event = true;
+    }
+
+    // selects just the handle `step` after the last one selected, wrapping around. with none
+    // selected, it starts from the first.
+    fn select_next(&mut self, step: usize) {
+        let count = self.handles.len();
+        if count == 0 {
+            return;
+        }
+        let next = match self.handles.iter().rposition(|h| h.selected) {
+            Some(i) => (i + step) % count,
+            None => 0,
+        };
+        for (i, handle) in self.handles.iter_mut().enumerate() {
+            handle.selected = i == next;
+        }
+    }
+
+    // moves the selected handles by dx, dy screen pixels.
+    fn nudge(&mut self, dx: f64, dy: f64) {
+        let scale = self.renderer.view().scale;
+        self.move_selected(dx / scale, dy / scale);
+    }
+
+    pub fn handle_count(&self) -> u32 {
+        self.handles.len() as u32
+    }
+
+    // where handle `handle` is in the scene, as x, y, z.
+    pub fn handle_position(&self, handle: u32) -> Result<Vec<f64>, JsValue> {
+        let pos = &self.handle(handle)?.pos;
+        Ok(vec![pos.x, pos.y, pos.z])
+    }
+
+    // puts handle `handle` at exactly x, y in the scene, at the same depth.
+    pub fn set_handle_position(&mut self, handle: u32, x: f64, y: f64) -> Result<(), JsValue> {
+        let z = self.handle(handle)?.pos.z;
+        self.history.record(self.snapshot());
+        self.handles[handle as usize].pos = Vec3::new(x, y, z);
+        self.user_event = true;
+        Ok(())
+    }
+
+    // moves handle `handle` to depth `z`: negative is toward the viewer, positive away.
+    pub fn set_handle_depth(&mut self, handle: u32, z: f64) -> Result<(), JsValue> {
+        self.handle(handle)?;
+        if !z.is_finite() {
+            return Err(JsValue::from_str(&format!("handle depth must be a number, not {}", z)));
+        }
+        self.history.record(self.snapshot());
+        self.handles[handle as usize].pos.z = z;
         self.user_event = true;
+        Ok(())
     }
 
-    pub fn handle_mouse_move(&mut self, x: f64, y: f64) {
-        self.update_mouse(x, y);
+    pub fn selected_handles(&self) -> Vec<u32> {
+        (0..self.handles.len() as u32)
+            .filter(|&i| self.handles[i as usize].selected)
+            .collect()
+    }
+
+    // selects exactly the given handles; ones that don't exist are ignored.
+    pub fn select_handles(&mut self, handles: &[u32]) {
+        for (i, handle) in self.handles.iter_mut().enumerate() {
+            handle.selected = handles.contains(&(i as u32));
+        }
+        self.user_event = true;
+    }
+
+    // moves the selected handles by dx, dy in the scene.
+    pub fn move_selected(&mut self, dx: f64, dy: f64) {
+        if !self.handles.iter().any(|h| h.selected) {
+            return;
+        }
+        self.history.record(self.snapshot());
+        let offset = Vec3::new(dx, dy, 0.);
+        for handle in self.handles.iter_mut().filter(|h| h.selected) {
+            handle.pos = &handle.pos + &offset;
+        }
+        self.user_event = true;
+    }
+
+    // snaps dragged handles to a grid this far apart in the scene, or not at all with 0.
+    pub fn set_grid_snap(&mut self, spacing: f64) {
+        self.snap.grid = if spacing > 0. { Some(spacing) } else { None };
+    }
+
+    // snaps the direction from a dragged handle to its neighbor to multiples of `degrees`, or
+    // not at all with 0.
+    pub fn set_angle_snap(&mut self, degrees: f64) {
+        self.snap.angle = angle_step(degrees);
+    }
+
+    fn handle(&self, handle: u32) -> Result<&Handle, JsValue> {
+        self.handles.get(handle as usize)
+            .ok_or_else(|| JsValue::from_str(&format!("no handle {}", handle)))
+    }
+
+    // a mouse button, pen or finger pressed on the canvas, from a pointerdown event. a
+    // pointerType that isn't recognized is treated as a mouse.
+    pub fn handle_pointer_down(&mut self, event: &web_sys::PointerEvent) {
+        let pointer = self.pointer(event);
+        let id = pointer.id;
+        if self.pointers.press(pointer.clone()) {
+            // a second finger turns the touch into a pinch, dropping whatever the first grabbed.
+            self.end_drag();
+            self.selecting = None;
+            self.panning = None;
+        }
+        if self.pointers.is_primary(id) {
+            if pointer.is_pressed(PRIMARY_BUTTON) {
+                self.press(&pointer);
+            } else if pointer.is_pressed(MIDDLE_BUTTON | SECONDARY_BUTTON) {
+                // other mouse buttons drag the view around.
+                self.panning = Some((pointer.x, pointer.y));
+            }
+        }
+        self.user_event = true;
+    }
+
+    // from a pointermove event, whether the pointer is down or just hovering.
+    pub fn handle_pointer_move(&mut self, event: &web_sys::PointerEvent) {
+        let pointer = self.pointer(event);
+        let id = pointer.id;
+        if let Some(pinch) = self.pointers.moved(pointer.clone()) {
+            let mut view = self.renderer.view().clone();
+            view.zoom_about(pinch.x, pinch.y, pinch.scale);
+            view.pan(pinch.dx, pinch.dy);
+            self.set_view(view);
+            return;
+        }
+        // other pointers hovering while one is down, or the fingers of a pinch as they lift,
+        // don't do anything.
+        if !self.pointers.is_primary(id) && !self.pointers.is_empty() {
+            return;
+        }
+        if let Some((last_x, last_y)) = self.panning {
+            let mut view = self.renderer.view().clone();
+            view.pan(pointer.x - last_x, pointer.y - last_y);
+            self.set_view(view);
+            self.panning = Some((pointer.x, pointer.y));
+            return;
+        }
+        self.move_pointer(&pointer);
+    }
+
+    // from a pointerup event.
+    pub fn handle_pointer_up(&mut self, event: &web_sys::PointerEvent) {
+        let pointer = self.pointer(event);
+        let id = pointer.id;
+        if self.pointers.is_primary(id) && self.panning.take().is_none() {
+            self.release(&pointer);
+        }
+        self.pointers.release(id);
+        self.user_event = true;
+    }
+
+    // the browser took the pointer over, say to scroll; a dragged handle is left where it got to.
+    pub fn handle_pointer_cancel(&mut self, id: i32) {
+        if self.pointers.is_primary(id) {
+            self.end_drag();
+            self.selecting = None;
+            self.panning = None;
+        }
+        self.pointers.release(id);
+        self.user_event = true;
+    }
+
+    // zooms about x, y for a wheel scrolled `delta` pixels; scrolling up zooms in.
+    pub fn handle_wheel(&mut self, x: f64, y: f64, delta: f64) {
+        let (x, y) = self.window_to_canvas((x, y));
+        let mut view = self.renderer.view().clone();
+        view.zoom_about(x, y, (-delta * WHEEL_ZOOM).exp());
+        self.set_view(view);
+    }
+
+    fn pointer(&self, event: &web_sys::PointerEvent) -> Pointer {
+        let (x, y) = self.window_to_canvas((event.offset_x() as f64, event.offset_y() as f64));
+        // in the order of their bits in `Pointer::modifiers`.
+        let held = [event.shift_key(), event.alt_key(), event.ctrl_key(), event.meta_key()];
+        Pointer {
+            id: event.pointer_id(),
+            kind: PointerKind::from_name(&event.pointer_type()).unwrap_or(PointerKind::Mouse),
+            x,
+            y,
+            pressure: event.pressure() as f64,
+            buttons: event.buttons() as u32,
+            modifiers: held.iter().enumerate().fold(0, |bits, (i, &down)| bits | (down as u32) << i),
+        }
+    }
+
+    fn move_pointer(&mut self, pointer: &Pointer) {
+        self.update_mouse(pointer);
         if let Some(i) = self.dragging_handle {
-            self.handles[i].pos = self.mouse.clone();
+            self.drag_to(i);
+        }
+        if let Some(grip) = self.dragging_grip {
+            self.drag_grip(grip);
+        }
+        if let Some(select) = &mut self.selecting {
+            select.end = (pointer.x, pointer.y);
         }
         for i in 0..self.handles.len() {
-            let hovering = self.handles[i].contains_mouse(&self.mouse);
+            let hovering = self.handles[i].contains_mouse(&self.mouse, self.grab_radius);
             self.handles[i].set_hovered(hovering);
         }
 
         self.user_event = true;
     }
 
-    pub fn handle_mouse_down(&mut self, x: f64, y: f64) {
-        self.update_mouse(x, y);
+    fn press(&mut self, pointer: &Pointer) {
+        self.update_mouse(pointer);
 
-        for i in 0..self.handles.len() {
-            if self.handles[i].contains_mouse(&self.mouse) {
+        // the grips of the branch or leaf being edited sit over the plant, so they come first.
+        if let Some(grip) = self.grip_at_mouse() {
+            self.dragging_grip = Some(grip);
+            self.before_drag = Some(self.snapshot());
+            return;
+        }
+
+        let shift = pointer.has_modifier(SHIFT_KEY);
+        let pressed = (0..self.handles.len())
+            .find(|&i| self.handles[i].contains_mouse(&self.mouse, self.grab_radius));
+        match pressed {
+            // shift-clicking a selected handle deselects it.
+            Some(i) if shift && self.handles[i].selected => self.handles[i].selected = false,
+            // pressing a handle selects it, along with the rest of the selection if it was
+            // already in it or shift is held, so they all move together.
+            Some(i) => {
+                if !shift && !self.handles[i].selected {
+                    self.handles.iter_mut().for_each(|h| h.selected = false);
+                }
+                self.handles[i].selected = true;
                 self.dragging_handle = Some(i);
-                break;
+                self.drag_start = self.positions();
+                // alt-dragging pushes the handles away from the viewer, dragging up, or pulls
+                // them toward it, dragging down.
+                self.drag_origin = self.mouse.clone();
+                self.dragging_depth = pointer.has_modifier(ALT_KEY);
+                self.before_drag = Some(self.snapshot());
             }
+            // pressing anywhere else drags out a box to select.
+            None => self.selecting = Some(SelectBox {
+                start: (pointer.x, pointer.y),
+                end: (pointer.x, pointer.y),
+                additive: shift,
+            }),
         }
 
         log(&format!("handles: {}", self.handles.iter()
             .map(|h| h.pos.to_string())
             .collect::<Vec<_>>()
             .join(", ")));
-
-        self.user_event = true;
     }
 
-    pub fn handle_mouse_up(&mut self, x: f64, y: f64) {
-        self.update_mouse(x, y);
+    fn release(&mut self, pointer: &Pointer) {
+        self.update_mouse(pointer);
 
-        if let Some(i) = self.dragging_handle.clone() {
-            self.handles[i].pos = self.mouse.clone();
-            self.dragging_handle = None;
+        if let Some(i) = self.dragging_handle {
+            self.drag_to(i);
+        }
+        if let Some(grip) = self.dragging_grip {
+            self.drag_grip(grip);
+        }
+        self.end_drag();
+        if let Some(select) = self.selecting.take() {
+            let rect = select.rect();
+            if rect.width < CLICK_DISTANCE && rect.height < CLICK_DISTANCE {
+                // a click picks the branch or leaf under it to edit.
+                self.component = self.renderer.pick(&self.flower, pointer.x, pointer.y)
+                    .map(|pt| self.flower.component_at(&pt))
+                    .filter(|c| *c != Component::Stem);
+            }
+            let view = self.renderer.view();
+            for handle in &mut self.handles {
+                let pos = view.to_screen(&handle.pos);
+                handle.selected = rect.contains(pos.x, pos.y) || (select.additive && handle.selected);
+            }
         }
         self.is_click_frame = true;
+    }
+
+    // moves handle `i` to the pointer, snapped, or in depth as far as the pointer's moved up or
+    // down, and the rest of the selection along with it.
+    fn drag_to(&mut self, i: usize) {
+        let start = &self.drag_start[i];
+        let offset = if self.dragging_depth {
+            Vec3::new(0., 0., self.drag_origin.y - self.mouse.y)
+        } else {
+            // angles snap around a neighbor that stays put: the handle before, or else after.
+            let anchor = [i.wrapping_sub(1), i + 1].iter()
+                .filter_map(|&j| self.handles.get(j))
+                .find(|h| !h.selected)
+                .map(|h| h.pos.clone());
+            let target = self.snap.apply(&self.mouse, anchor.as_ref());
+            Vec3::new(target.x - start.x, target.y - start.y, 0.)
+        };
+        if self.handles[i].pos != start + &offset {
+            self.edited();
+        }
+        for (handle, start) in self.handles.iter_mut().zip(&self.drag_start) {
+            if handle.selected {
+                handle.pos = start + &offset;
+            }
+        }
+    }
+
+    // moves a grip of the branch or leaf being edited to the pointer, snapped, at the depth
+    // it's at. a branch's other grips angle-snap around where it attaches.
+    fn drag_grip(&mut self, grip: Grip) {
+        let component = match self.component {
+            Some(component) => component,
+            None => return,
+        };
+        let grips = self.flower.grips(component);
+        let current = match grips.iter().find(|(g, _)| *g == grip) {
+            Some((_, pos)) => pos,
+            None => return,
+        };
+        let anchor = grips.iter()
+            .find(|(g, _)| *g == Grip::Attach && grip != Grip::Attach)
+            .map(|(_, pos)| pos);
+        let target = self.snap.apply(&Vec3::new(self.mouse.x, self.mouse.y, current.z), anchor);
+        if target != *current {
+            self.edited();
+            self.flower.move_grip(component, grip, &target);
+        }
+    }
+
+    // the grip of the branch or leaf being edited under the pointer, if any.
+    fn grip_at_mouse(&self) -> Option<Grip> {
+        let component = self.component?;
+        let radius = self.grab_radius;
+        self.flower.grips(component).into_iter()
+            .find(|(_, pos)| (pos.x - self.mouse.x).hypot(pos.y - self.mouse.y) < radius)
+            .map(|(grip, _)| grip)
+    }
+
+    fn end_drag(&mut self) {
+        self.dragging_handle = None;
+        self.dragging_depth = false;
+        self.dragging_grip = None;
+        self.before_drag = None;
+        self.drag_start.clear();
+    }
+
+    fn set_view(&mut self, view: View) {
+        self.renderer.set_view(view);
+        self.region_changed = true;
         self.user_event = true;
     }
 
@@ -372,12 +1332,39 @@ impl Canvas {
         self.g.set_stroke_style(&color.as_hexstring().into())
     }
 
-    fn update_mouse(&mut self, x: f64, y: f64) {
-        let (x, y) = self.window_to_canvas((x, y));
-        self.mouse = Vec3::new(x, y, 0.);
+    fn update_mouse(&mut self, pointer: &Pointer) {
+        let view = self.renderer.view();
+        self.mouse = view.to_scene(&Vec3::new(pointer.x, pointer.y, 0.));
+        let radius = if pointer.kind == PointerKind::Touch { TOUCH_RADIUS } else { MOUSE_RADIUS };
+        self.grab_radius = radius / view.scale;
+        self.pressure = pointer.pressure;
     }
 
     fn window_to_canvas(&self, w: (f64, f64)) -> (f64, f64) {
         (w.0 - self.canvas.offset_left() as f64, w.1 - self.canvas.offset_top() as f64)
     }
 }
+
+// thorns and sepals need a real, positive size; any curvature is clamped to [0, 1], so it just
+// needs to be a number.
+fn check_shape(part: &str, size: f64, curvature: f64) -> Result<(), JsValue> {
+    if !(size.is_finite() && size > 0.) {
+        return Err(JsValue::from_str(&format!("{} size must be a positive number, not {}", part, size)));
+    }
+    if curvature.is_nan() {
+        return Err(JsValue::from_str(&format!("{} curvature must be a number", part)));
+    }
+    Ok(())
+}
+
+// a keyframe has to land somewhere on the timeline, at a real position or value; a nan or
+// infinite one would poison every frame it eases into.
+fn check_keyframe(seconds: f64, values: &[f64]) -> Result<(), JsValue> {
+    if !(seconds.is_finite() && seconds >= 0.) {
+        return Err(JsValue::from_str(&format!("keyframe time must be 0 or more seconds, not {}", seconds)));
+    }
+    if !values.iter().all(|v| v.is_finite()) {
+        return Err(JsValue::from_str("a keyframe's values must be numbers"));
+    }
+    Ok(())
+}
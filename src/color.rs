@@ -1,40 +1,135 @@
+use std::error::Error;
 use std::fmt;
 use std::ops;
+use std::str::FromStr;
 
-#[derive(Clone, Debug)]
+// a color in linear-light rgb, with straight (not premultiplied) alpha. lighting and blending
+// math happens on these values directly; the srgb constructors and accessors convert to and
+// from the gamma-encoded values that css, hex strings and color pickers use.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Color {
     r: f64,
     g: f64,
     b: f64,
+    a: f64,
 }
 
 impl Color {
     pub fn new(r: f64, g: f64, b: f64) -> Self {
-        Self { r, g, b }
+        Self { r, g, b, a: 1. }
+    }
+
+    pub fn from_srgb(r: f64, g: f64, b: f64) -> Self {
+        Self::new(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
     }
 
     pub fn from_irgb(r: usize, g: usize, b: usize) -> Self {
-        Self::new(
+        Self::from_srgb(
             (r as f64) / 255.,
             (g as f64) / 255.,
             (b as f64) / 255.,
         )
     }
 
-    pub fn from_hexstring(color: &str) -> Self {
-        let color = if color.starts_with('#') {
-            &color[1..]
-        } else {
-            &color
+    // #rgb, #rgba, #rrggbb or #rrggbbaa; the leading '#' is optional.
+    pub fn from_hexstring(color: &str) -> Result<Self, ColorParseError> {
+        let digits = color.strip_prefix('#').unwrap_or(color);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorParseError::InvalidHex(color.to_string()));
+        }
+
+        let channel = |i: usize, width: usize| {
+            let v = u32::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            if width == 1 { v * 17 } else { v }
+        };
+        let (width, has_alpha) = match digits.len() {
+            3 => (1, false),
+            4 => (1, true),
+            6 => (2, false),
+            8 => (2, true),
+            _ => return Err(ColorParseError::InvalidHex(color.to_string())),
         };
 
-        assert_eq!(color.len(), 6, "color string must contain six characters.");
+        let color = Self::from_irgb(
+            channel(0, width) as usize,
+            channel(1, width) as usize,
+            channel(2, width) as usize,
+        );
+        if has_alpha {
+            return Ok(color.with_alpha(channel(3, width) as f64 / 255.));
+        }
+        Ok(color)
+    }
+
+    // parses any of the css color syntaxes users are likely to type: hex strings, the rgb(),
+    // rgba(), hsl() and hsla() functions (with either comma or space separated arguments), and
+    // named colors.
+    pub fn parse(color: &str) -> Result<Self, ColorParseError> {
+        let color = color.trim();
+        if color.is_empty() {
+            return Err(ColorParseError::Empty);
+        }
+        if color.starts_with('#') {
+            return Self::from_hexstring(color);
+        }
+
+        let lower = color.to_ascii_lowercase();
+        if let Some(open) = lower.find('(') {
+            if !lower.ends_with(')') {
+                return Err(ColorParseError::InvalidFunction(color.to_string()));
+            }
+            let name = lower[..open].trim();
+            let args = parse_arguments(&lower[open + 1..lower.len() - 1])
+                .ok_or_else(|| ColorParseError::InvalidFunction(color.to_string()))?;
+            return match name {
+                "rgb" | "rgba" => parse_rgb_function(&args),
+                "hsl" | "hsla" => parse_hsl_function(&args),
+                _ => Err(ColorParseError::UnknownFunction(name.to_string())),
+            };
+        }
+
+        NAMED_COLORS.iter()
+            .find(|(name, _)| *name == lower)
+            .map(|(name, rgb)| {
+                let color = Self::from_irgb(
+                    (rgb >> 16) as usize & 0xff,
+                    (rgb >> 8) as usize & 0xff,
+                    *rgb as usize & 0xff,
+                );
+                if *name == "transparent" { color.with_alpha(0.) } else { color }
+            })
+            .ok_or_else(|| ColorParseError::UnknownName(color.to_string()))
+    }
 
-        let r = usize::from_str_radix(&color[0..2], 16).unwrap();
-        let g = usize::from_str_radix(&color[2..4], 16).unwrap();
-        let b = usize::from_str_radix(&color[4..6], 16).unwrap();
+    // hue in degrees; saturation and value in [0, 1]. like css, these are srgb-encoded.
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Self {
+        let c = v * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = v - c;
+        Self::from_srgb(r + m, g + m, b + m)
+    }
 
-        Self::from_irgb(r, g, b)
+    // hue in degrees; saturation and lightness in [0, 1]. like css, these are srgb-encoded.
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Self {
+        let c = (1. - (2. * l - 1.).abs()) * s;
+        let (r, g, b) = hue_to_rgb(h, c);
+        let m = l - c / 2.;
+        Self::from_srgb(r + m, g + m, b + m)
+    }
+
+    // https://bottosson.github.io/posts/oklab/
+    pub fn from_oklab(l: f64, a: f64, b: f64) -> Self {
+        let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+        let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+        let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+
+        let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+
+        Self::new(
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        )
     }
 
     pub fn black() -> Self {
@@ -45,6 +140,19 @@ impl Color {
         Self::new(1., 1., 1.)
     }
 
+    pub fn transparent() -> Self {
+        Self::black().with_alpha(0.)
+    }
+
+    pub fn with_alpha(mut self, a: f64) -> Self {
+        self.a = a;
+        self
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.a
+    }
+
     pub fn is_black(&self) -> bool {
         self.r.max(self.g).max(self.b) <= 0.0
     }
@@ -53,13 +161,47 @@ impl Color {
         self.r.min(self.g).min(self.b) >= 1.0
     }
 
+    pub fn to_srgb(&self) -> (f64, f64, f64) {
+        (linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b))
+    }
+
+    pub fn to_oklab(&self) -> (f64, f64, f64) {
+        let l = 0.4122214708 * self.r + 0.5363325363 * self.g + 0.0514459929 * self.b;
+        let m = 0.2119034982 * self.r + 0.6806995451 * self.g + 0.1073969566 * self.b;
+        let s = 0.0883024619 * self.r + 0.2817188376 * self.g + 0.6299787005 * self.b;
+
+        let (l_, m_, s_) = (l.cbrt(), m.cbrt(), s.cbrt());
+
+        (
+            0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+            1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+            0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_,
+        )
+    }
+
+    // interpolates in linear light, which is physically correct for mixing light (e.g. blurs).
     pub fn lerp(mut self, s: f64, other: &Color) -> Self {
         self.r = (1.0 - s) * self.r + s * other.r;
         self.g = (1.0 - s) * self.g + s * other.g;
         self.b = (1.0 - s) * self.b + s * other.b;
+        self.a = (1.0 - s) * self.a + s * other.a;
         self
     }
 
+    // interpolates in oklab, which keeps perceived lightness and hue even between the two ends.
+    // better for palettes and gradients.
+    pub fn lerp_oklab(self, s: f64, other: &Color) -> Self {
+        let (l0, a0, b0) = self.to_oklab();
+        let (l1, a1, b1) = other.to_oklab();
+        Self::from_oklab(
+            (1.0 - s) * l0 + s * l1,
+            (1.0 - s) * a0 + s * a1,
+            (1.0 - s) * b0 + s * b1,
+        ).with_alpha((1.0 - s) * self.a + s * other.a)
+    }
+
+    // the arithmetic below only touches the color channels; alpha is carried along from self.
+
     pub fn add(mut self, scale: f64, other: &Color) -> Self {
         self.r += scale * other.r;
         self.g += scale * other.g;
@@ -81,16 +223,161 @@ impl Color {
         self
     }
 
+    // raises each channel to a power, e.g. to layer a transmittance `e` times over itself.
+    pub fn powf(mut self, e: f64) -> Self {
+        self.r = self.r.max(0.).powf(e);
+        self.g = self.g.max(0.).powf(e);
+        self.b = self.b.max(0.).powf(e);
+        self
+    }
+
+    // srgb-encoded #rrggbb, or #rrggbbaa when the color isn't fully opaque.
     pub fn as_hexstring(&self) -> String {
-        let r = convert_to_255(self.r);
-        let g = convert_to_255(self.g);
-        let b = convert_to_255(self.b);
+        let (r, g, b) = self.to_srgb();
+        let (r, g, b) = (convert_to_255(r), convert_to_255(g), convert_to_255(b));
+        if self.a < 1. {
+            return format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, convert_to_255(self.a));
+        }
         format!("#{:02x}{:02x}{:02x}", r, g, b)
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColorParseError {
+    Empty,
+    InvalidHex(String),
+    InvalidFunction(String),
+    UnknownFunction(String),
+    InvalidComponent(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            ColorParseError::Empty => write!(f, "color string is empty"),
+            ColorParseError::InvalidHex(s) =>
+                write!(f, "'{}' is not a #rgb, #rgba, #rrggbb or #rrggbbaa hex color", s),
+            ColorParseError::InvalidFunction(s) => write!(f, "malformed color function '{}'", s),
+            ColorParseError::UnknownFunction(s) => write!(f, "unknown color function '{}'", s),
+            ColorParseError::InvalidComponent(s) => write!(f, "invalid color component '{}'", s),
+            ColorParseError::UnknownName(s) => write!(f, "unknown color name '{}'", s),
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s)
+    }
+}
+
+// splits "1, 2, 3" or "1 2 3 / 0.5" into its components, with alpha (if any) last.
+fn parse_arguments(args: &str) -> Option<Vec<&str>> {
+    let (channels, alpha) = match args.find('/') {
+        Some(slash) => (&args[..slash], Some(args[slash + 1..].trim())),
+        None => (args, None),
+    };
+    let mut parts: Vec<&str> = if channels.contains(',') {
+        if alpha.is_some() {
+            return None;
+        }
+        channels.split(',').map(|p| p.trim()).collect()
+    } else {
+        channels.split_whitespace().collect()
+    };
+    if let Some(alpha) = alpha {
+        if parts.len() != 3 {
+            return None;
+        }
+        parts.push(alpha);
+    }
+    if parts.len() < 3 || parts.len() > 4 || parts.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+    Some(parts)
+}
+
+fn parse_number(s: &str) -> Result<f64, ColorParseError> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| ColorParseError::InvalidComponent(s.to_string()))
+}
+
+// a number scaled so `max` maps to 1, or a percentage.
+fn parse_fraction(s: &str, max: f64) -> Result<f64, ColorParseError> {
+    let v = match s.strip_suffix('%') {
+        Some(pct) => parse_number(pct)? / 100.,
+        None => parse_number(s)? / max,
+    };
+    Ok(v.clamp(0., 1.))
+}
+
+fn parse_alpha(args: &[&str]) -> Result<f64, ColorParseError> {
+    match args.get(3) {
+        Some(a) => parse_fraction(a, 1.),
+        None => Ok(1.),
+    }
+}
+
+fn parse_rgb_function(args: &[&str]) -> Result<Color, ColorParseError> {
+    Ok(Color::from_srgb(
+        parse_fraction(args[0], 255.)?,
+        parse_fraction(args[1], 255.)?,
+        parse_fraction(args[2], 255.)?,
+    ).with_alpha(parse_alpha(args)?))
+}
+
+fn parse_hsl_function(args: &[&str]) -> Result<Color, ColorParseError> {
+    let hue = args[0].strip_suffix("deg").unwrap_or(args[0]);
+    let percent = |s: &str| match s.strip_suffix('%') {
+        Some(pct) => Ok((parse_number(pct)? / 100.).clamp(0., 1.)),
+        None => Err(ColorParseError::InvalidComponent(s.to_string())),
+    };
+    Ok(Color::from_hsl(
+        parse_number(hue)?,
+        percent(args[1])?,
+        percent(args[2])?,
+    ).with_alpha(parse_alpha(args)?))
+}
+
 fn convert_to_255(f: f64) -> usize {
-    (f.max(0.).min(1.0) * 255.) as usize
+    (f.clamp(0., 1.) * 255.).round() as usize
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+// the (r, g, b) of a fully saturated hue with chroma c, before adding the lightness offset.
+fn hue_to_rgb(h: f64, c: f64) -> (f64, f64, f64) {
+    let h = h.rem_euclid(360.) / 60.;
+    let x = c * (1. - (h % 2. - 1.).abs());
+    match h as usize {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    }
 }
 
 impl fmt::Display for Color {
@@ -139,6 +426,159 @@ impl ops::Mul<&Color> for f64 {
     }
 }
 
+// https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("transparent", 0x000000),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
 #[cfg(test)]
 mod tests {
     use crate::color::*;
@@ -148,7 +588,76 @@ mod tests {
         assert_eq!("#0fff08", Color::from_irgb(15, 255, 8).as_hexstring());
         assert_eq!(Color::white().to_string(), "#ffffff");
         assert_eq!(Color::black().to_string(), "#000000");
-        assert_eq!(Color::from_hexstring("#ffffff").to_string(), "#ffffff");
-        assert_eq!(Color::from_hexstring("#000000").to_string(), "#000000");
+        assert_eq!(Color::from_hexstring("#ffffff").unwrap().to_string(), "#ffffff");
+        assert_eq!(Color::from_hexstring("#000000").unwrap().to_string(), "#000000");
+        assert_eq!(Color::from_hexstring("#ff000080").unwrap().to_string(), "#ff000080");
+    }
+
+    #[test]
+    fn srgb_roundtrip() {
+        for i in 0..256 {
+            let hex = format!("#{:02x}{:02x}{:02x}", i, 255 - i, i / 2);
+            assert_eq!(hex, Color::from_hexstring(&hex).unwrap().as_hexstring());
+        }
+        // mid-grey in srgb is much darker than half in linear light.
+        assert_eq!("#bcbcbc", Color::new(0.5, 0.5, 0.5).as_hexstring());
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        assert_eq!("#ff0000", Color::from_hsv(0., 1., 1.).as_hexstring());
+        assert_eq!("#00ff00", Color::from_hsv(120., 1., 1.).as_hexstring());
+        assert_eq!("#0000ff", Color::from_hsl(240., 1., 0.5).as_hexstring());
+        assert_eq!("#ffffff", Color::from_hsl(17., 0.3, 1.).as_hexstring());
+        assert_eq!("#bf40bf", Color::from_hsl(300., 0.5, 0.5).as_hexstring());
+    }
+
+    #[test]
+    fn oklab() {
+        let (l, a, b) = Color::white().to_oklab();
+        assert!((l - 1.).abs() < 1e-4 && a.abs() < 1e-4 && b.abs() < 1e-4);
+
+        let red = Color::from_hexstring("#ff0000").unwrap();
+        let (l, a, b) = red.to_oklab();
+        assert_eq!("#ff0000", Color::from_oklab(l, a, b).as_hexstring());
+        assert_eq!("#ff0000", red.clone().lerp_oklab(0., &Color::white()).as_hexstring());
+        assert_eq!("#ffffff", red.lerp_oklab(1., &Color::white()).as_hexstring());
+    }
+
+    #[test]
+    fn parse() {
+        let hex = |s: &str| Color::parse(s).map(|c| c.as_hexstring());
+        assert_eq!(Ok("#ff0000".to_string()), hex("#f00"));
+        assert_eq!(Ok("#ff000088".to_string()), hex("#f008"));
+        assert_eq!(Ok("#123456".to_string()), hex(" #123456 "));
+        assert_eq!(Ok("#00ff0080".to_string()), hex("rgba(0, 255, 0, 0.5)"));
+        assert_eq!(Ok("#0000ff80".to_string()), hex("rgb(0 0 100% / 50%)"));
+        assert_eq!(Ok("#bf40bf".to_string()), hex("hsl(300deg, 50%, 50%)"));
+        assert_eq!(Ok("#ff6347".to_string()), hex("Tomato"));
+        assert_eq!(Ok("#00000000".to_string()), hex("transparent"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Err(ColorParseError::Empty), Color::parse("  "));
+        assert_eq!(Err(ColorParseError::InvalidHex("#12345".to_string())), Color::parse("#12345"));
+        assert_eq!(Err(ColorParseError::InvalidHex("#ggg".to_string())), Color::parse("#ggg"));
+        assert_eq!(Err(ColorParseError::InvalidHex("#é".to_string())), Color::parse("#é"));
+        assert_eq!(
+            Err(ColorParseError::InvalidComponent("x".to_string())),
+            Color::parse("rgb(1, 2, x)"),
+        );
+        assert_eq!(
+            Err(ColorParseError::InvalidFunction("rgb(1, 2)".to_string())),
+            Color::parse("rgb(1, 2)"),
+        );
+        assert_eq!(
+            Err(ColorParseError::UnknownFunction("lab".to_string())),
+            Color::parse("lab(1 2 3)"),
+        );
+        assert_eq!(
+            Err(ColorParseError::UnknownName("rosé".to_string())),
+            Color::parse("rosé"),
+        );
     }
 }
@@ -0,0 +1,262 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::threed::Vec3;
+
+// every frame is kept as rgba pixels until the export is written out, so the whole sequence has
+// to fit in this many bytes.
+const MAX_EXPORT_BYTES: usize = 512 << 20;
+
+// the most frames an export of the given size can hold.
+pub fn max_frames(width: u32, height: u32) -> usize {
+    MAX_EXPORT_BYTES / (width as usize * height as usize * 4).max(1)
+}
+
+// what changes from frame to frame of an exported sequence.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sequence {
+    // the plant growing from a seedling to fully grown.
+    Growth,
+    // one full turn of the plant around its stem, ending just short of where it started so the
+    // sequence loops.
+    Turntable,
+    // handle positions moving through each set of keyframes in turn, evenly spaced in time.
+    Handles(Vec<Vec<Vec3>>),
+}
+
+// the state of the plant for one frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameState {
+    Growth(f64),
+    Turn(f64),
+    Handles(Vec<Vec3>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportError {
+    NoFrames,
+    // more frames than fit in memory; holds the most there can be.
+    TooManyFrames(usize),
+    // the keyframes at the given index don't have one position per handle.
+    MismatchedKeyframe(usize),
+    // still rendering; holds how many frames are done.
+    Unfinished(usize),
+    Encoding(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            ExportError::NoFrames => write!(f, "an export needs at least one frame"),
+            ExportError::TooManyFrames(max) => write!(f, "an export this size can have at most {} frames", max),
+            ExportError::MismatchedKeyframe(i) =>
+                write!(f, "keyframe {} doesn't have a position for every handle", i),
+            ExportError::Unfinished(done) => write!(f, "the export is still rendering ({} frames done)", done),
+            ExportError::Encoding(e) => write!(f, "couldn't encode png: {}", e),
+        }
+    }
+}
+
+impl Error for ExportError {}
+
+impl From<png::EncodingError> for ExportError {
+    fn from(e: png::EncodingError) -> Self {
+        ExportError::Encoding(e.to_string())
+    }
+}
+
+// a sequence of frames being rendered for export, collected as rgba pixels, then written out as
+// numbered pngs or one animated png.
+pub struct Export {
+    sequence: Sequence,
+    frame_count: usize,
+    fps: f64,
+    width: u32,
+    height: u32,
+    frames: Vec<Vec<u8>>,
+    // whether the next frame's state has been handed out and is waiting to be rendered.
+    rendering: bool,
+}
+
+impl Export {
+    pub fn new(sequence: Sequence, frame_count: usize, fps: f64, width: u32, height: u32) -> Result<Self, ExportError> {
+        if frame_count == 0 {
+            return Err(ExportError::NoFrames);
+        }
+        if frame_count > max_frames(width, height) {
+            return Err(ExportError::TooManyFrames(max_frames(width, height)));
+        }
+        if let Sequence::Handles(keyframes) = &sequence {
+            if keyframes.is_empty() {
+                return Err(ExportError::NoFrames);
+            }
+            if let Some(i) = keyframes.iter().position(|k| k.len() != keyframes[0].len()) {
+                return Err(ExportError::MismatchedKeyframe(i));
+            }
+        }
+        Ok(Self {
+            sequence,
+            frame_count,
+            fps: fps.max(1.),
+            width,
+            height,
+            frames: vec![],
+            rendering: false,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.len() == self.frame_count
+    }
+
+    pub fn is_rendering(&self) -> bool {
+        self.rendering
+    }
+
+    // how much of it is done, in [0, 1].
+    pub fn progress(&self) -> f64 {
+        self.frames.len() as f64 / self.frame_count as f64
+    }
+
+    // the state to render for the next frame, or none if every frame is done.
+    pub fn next_frame(&mut self) -> Option<FrameState> {
+        if self.is_finished() {
+            return None;
+        }
+        self.rendering = true;
+        Some(self.state(self.frames.len()))
+    }
+
+    // the rendered pixels for the frame `next_frame` last handed out, as rgba rows.
+    pub fn add_frame(&mut self, rgba: Vec<u8>) {
+        self.frames.push(rgba);
+        self.rendering = false;
+    }
+
+    pub fn state(&self, frame: usize) -> FrameState {
+        // where the frame falls from the first to the last, in [0, 1].
+        let t = if self.frame_count > 1 { frame as f64 / (self.frame_count - 1) as f64 } else { 1. };
+        match &self.sequence {
+            Sequence::Growth => FrameState::Growth(t),
+            Sequence::Turntable => FrameState::Turn(PI * 2. * frame as f64 / self.frame_count as f64),
+            Sequence::Handles(keyframes) => {
+                let x = t * (keyframes.len() - 1) as f64;
+                let i = (x.floor() as usize).min(keyframes.len().saturating_sub(2));
+                let (a, b) = (&keyframes[i], keyframes.get(i + 1).unwrap_or(&keyframes[i]));
+                FrameState::Handles(a.iter()
+                    .zip(b)
+                    .map(|(a, b)| Vec3::lerp(a, b, x - i as f64))
+                    .collect())
+            }
+        }
+    }
+
+    // numbered from 1, padded so they sort in order.
+    pub fn frame_name(&self, frame: usize) -> String {
+        let digits = self.frame_count.to_string().len().max(4);
+        format!("frame_{:0width$}.png", frame + 1, width = digits)
+    }
+
+    pub fn png(&self, frame: usize) -> Result<Vec<u8>, ExportError> {
+        let rgba = self.frames.get(frame).ok_or(ExportError::Unfinished(self.frames.len()))?;
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgba)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    // every frame in one animated png, looping forever.
+    pub fn apng(&self) -> Result<Vec<u8>, ExportError> {
+        if !self.is_finished() {
+            return Err(ExportError::Unfinished(self.frames.len()));
+        }
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frame_count as u32, 0)?;
+        // delays are fractions of a second; milliseconds are plenty precise.
+        encoder.set_frame_delay((1000. / self.fps).round() as u16, 1000)?;
+        let mut writer = encoder.write_header()?;
+        for rgba in &self.frames {
+            writer.write_image_data(rgba)?;
+        }
+        writer.finish()?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::*;
+
+    fn solid(width: u32, height: u32, shade: u8) -> Vec<u8> {
+        vec![shade; (width * height * 4) as usize]
+    }
+
+    #[test]
+    fn frame_states() {
+        let growth = Export::new(Sequence::Growth, 5, 10., 1, 1).unwrap();
+        assert_eq!(FrameState::Growth(0.), growth.state(0));
+        assert_eq!(FrameState::Growth(0.5), growth.state(2));
+        assert_eq!(FrameState::Growth(1.), growth.state(4));
+
+        let turntable = Export::new(Sequence::Turntable, 4, 10., 1, 1).unwrap();
+        assert_eq!(FrameState::Turn(PI * 1.5), turntable.state(3));
+
+        let keyframes = vec![
+            vec![Vec3::new(0., 0., 0.)],
+            vec![Vec3::new(10., 0., 0.)],
+            vec![Vec3::new(10., 20., 0.)],
+        ];
+        let handles = Export::new(Sequence::Handles(keyframes), 5, 10., 1, 1).unwrap();
+        assert_eq!(FrameState::Handles(vec![Vec3::new(5., 0., 0.)]), handles.state(1));
+        assert_eq!(FrameState::Handles(vec![Vec3::new(10., 20., 0.)]), handles.state(4));
+    }
+
+    #[test]
+    fn bad_sequences() {
+        assert_eq!(Some(ExportError::NoFrames), Export::new(Sequence::Growth, 0, 10., 1, 1).err());
+        let keyframes = vec![vec![Vec3::zero()], vec![Vec3::zero(), Vec3::zero()]];
+        assert_eq!(
+            Some(ExportError::MismatchedKeyframe(1)),
+            Export::new(Sequence::Handles(keyframes), 3, 10., 1, 1).err());
+        let max = max_frames(800, 600);
+        assert!(Export::new(Sequence::Growth, max, 10., 800, 600).is_ok());
+        assert_eq!(
+            Some(ExportError::TooManyFrames(max)),
+            Export::new(Sequence::Growth, max + 1, 10., 800, 600).err());
+    }
+
+    #[test]
+    fn writes_pngs() {
+        let mut export = Export::new(Sequence::Growth, 3, 12., 2, 3).unwrap();
+        let mut shade = 0;
+        while export.next_frame().is_some() {
+            assert!(export.is_rendering());
+            assert_eq!(Err(ExportError::Unfinished(shade as usize)), export.apng().map(|_| ()));
+            export.add_frame(solid(2, 3, shade));
+            shade += 1;
+        }
+        assert_eq!("frame_0003.png", export.frame_name(2));
+
+        let png = export.png(1).unwrap();
+        let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(solid(2, 3, 1), buf);
+
+        let apng = export.apng().unwrap();
+        let reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+        assert_eq!(3, reader.info().animation_control.unwrap().num_frames);
+    }
+}
@@ -1,6 +1,12 @@
-use crate::sdf;
-use crate::sdf::{find_closest_point, sdf_curve};
-use crate::threed::{Vec3, Frame};
+use crate::instance::{aim, Instance, Instanced, Prototype};
+use crate::lsystem::{LSystem, LSystemError, Shoot};
+use crate::noise::{Noise, PERLIN3_SLOPE};
+use crate::phyllotaxis::Phyllotaxis;
+use crate::rng::Rng;
+use crate::sepal::{SepalPrototype, Sepals};
+use crate::sdf::{curve_bounds, find_closest_point, sdf_curve};
+use crate::thorn::{ThornPrototype, Thorns, THORN_DEPTH};
+use crate::threed::{perpendicular, tangent, Aabb, CurveFrames, Frame, Quat, Vec3};
 use crate::utils::{lerpf, exp_smin};
 
 struct LeafGen {
@@ -17,13 +23,22 @@ struct LeafGen {
 }
 
 impl LeafGen {
-    pub fn vein_distance<C: Fn(f64) -> (Vec3, f64)>(&self, midrib_curve: &C, pt: &Vec3) -> f64 {
+    pub fn new(vein_pairs: usize) -> Self {
+        Self {
+            vein_pairs,
+            base_offset: 0.1,
+            margin_shape: Box::new(|s: f64| Vec3::lerp(&Vec3::zero(), &Vec3::right(), s)),
+            vein_shape: Box::new(|s: f64| Vec3::lerp(&Vec3::zero(), &Vec3::right(), s)),
+        }
+    }
+
+    pub fn vein_distance_in(&self, frames: &[Frame], pt: &Vec3) -> f64 {
         let mut distance: Option<f64> = None;
 
-        for pair_no in 0..self.vein_pairs {
+        for (pair_no, frame) in frames.iter().enumerate() {
             let t = pair_no as f64 / self.vein_pairs as f64;
             let d = sdf_curve(
-                &|s| self.rib_point(midrib_curve, pair_no, s),
+                &|s| self.rib_point(frame, s),
                 &|s| 1.,
                 pt);
             distance = Some(match distance {
@@ -35,68 +50,714 @@ impl LeafGen {
         distance.unwrap()
     }
 
+    pub fn vein_bounds_in(&self, frames: &[Frame]) -> Aabb {
+        frames.iter()
+            .map(|frame| curve_bounds(&|s| self.rib_point(frame, s), 1.))
+            .fold(None as Option<Aabb>, |a, b| Some(match a {
+                None => b,
+                Some(a) => a.union(&b),
+            }))
+            .unwrap()
+    }
+
     pub fn base_position<C: Fn(f64) -> (Vec3, f64)>(&self, midrib_curve: &C) -> Vec3 {
         midrib_curve(self.base_offset).0
     }
 
-    pub fn rib_point<C: Fn(f64) -> (Vec3, f64)>(
-        &self,
-        midrib_curve: &C,
-        index: usize,
-        s: f64) -> Vec3 {
-        let start_t = lerpf(self.base_offset, 1., index as f64 / self.vein_pairs as f64);
-        let blade_frame = self.blade_frame(&midrib_curve, start_t);
-
+    pub fn rib_point(&self, blade_frame: &Frame, s: f64) -> Vec3 {
         let local: Vec3 = (self.vein_shape)(s);
 
         blade_frame.project(&local)
     }
 
-    pub fn blade_frame<C: Fn(f64) -> (Vec3, f64)>(&self, midrib_curve: &C, s: f64) -> Frame {
-        let (origin, _rotation) = midrib_curve(s);
+    // the frame at the start of each vein pair, scaled to the length of the blade. frames are
+    // rotation-minimizing along the midrib, starting with the top of the leaf facing the viewer,
+    // so the blade twists smoothly however the midrib bends.
+    pub fn blade_frames<C: Fn(f64) -> (Vec3, f64)>(&self, midrib_curve: &C) -> Vec<Frame> {
         let length: f64 = (&midrib_curve(1.).0 - &midrib_curve(self.base_offset).0).mag();
-        let (next_pos, _) = midrib_curve(s + 0.01);
-        let tipward: Vec3 = (&next_pos - &origin).unit();
-        let (upward, sidward) = {
-            let up = Vec3::new(0., 0., -1.);
-            let side = tipward.cross(&up);
-            if side.is_zero(0.0001) {
-                (Vec3::right(), tipward.cross(&Vec3::right()).unit())
-            } else {
-                (up, side.unit())
+        // a few steps between veins keeps the reflections accurate on tightly curled leaves.
+        let steps = 4;
+        let frames = CurveFrames::rotation_minimizing(
+            &|s| midrib_curve(s).0,
+            self.base_offset,
+            1.,
+            self.vein_pairs * steps,
+            &Vec3::new(0., 0., -1.));
+
+        (0..self.vein_pairs)
+            .map(|pair_no| frames.frames()[pair_no * steps].to_frame(length))
+            .collect()
+    }
+}
+
+// leaves are modeled this long in their own space, and scaled to size where they're placed.
+const LEAF_LENGTH: f64 = 100.;
+
+// a leaf blade in its own space: the midrib runs LEAF_LENGTH up +y from the origin, arching a
+// little toward -z (the top of the blade), with veins fanning out along x. variation makes the
+// blade narrower or wider.
+struct LeafPrototype {
+    leaf_gen: LeafGen,
+    blade_frames: Vec<Frame>,
+    bounds: Aabb,
+}
+
+impl LeafPrototype {
+    fn new(vein_pairs: usize) -> Self {
+        let leaf_gen = LeafGen::new(vein_pairs);
+        let blade_frames = leaf_gen.blade_frames(&|s| (Self::midrib(s), 0.));
+        let bounds = curve_bounds(&Self::midrib, Self::thickness(0.))
+            .union(&leaf_gen.vein_bounds_in(&blade_frames));
+        let widest = Self::width(1.);
+        let bounds = Aabb::new(
+            Vec3::new(bounds.min.x * widest, bounds.min.y, bounds.min.z),
+            Vec3::new(bounds.max.x * widest, bounds.max.y, bounds.max.z),
+        );
+        Self { leaf_gen, blade_frames, bounds }
+    }
+
+    fn midrib(s: f64) -> Vec3 {
+        Vec3::bezier2(
+            &Vec3::zero(),
+            &Vec3::new(0., LEAF_LENGTH * 0.5, -LEAF_LENGTH * 0.15),
+            &Vec3::new(0., LEAF_LENGTH, 0.),
+            s,
+        )
+    }
+
+    fn thickness(s: f64) -> f64 {
+        lerpf(3., 1., s)
+    }
+
+    fn width(variation: f64) -> f64 {
+        lerpf(0.8, 1.2, variation)
+    }
+}
+
+impl Prototype for LeafPrototype {
+    fn distance(&self, pt: &Vec3, variation: f64) -> f64 {
+        // squashing space sideways stretches distances by up to 1 / width, so scale them back
+        // down to stay conservative.
+        let width = Self::width(variation);
+        let pt = Vec3::new(pt.x / width, pt.y, pt.z);
+        let midrib = sdf_curve(&Self::midrib, &Self::thickness, &pt);
+        let veins = self.leaf_gen.vein_distance_in(&self.blade_frames, &pt);
+        exp_smin(midrib, veins, BLEND) * width.min(1.)
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds.clone()
+    }
+}
+
+// where a leaf grows on its stem or branch, and how it's turned there.
+#[derive(Clone, Debug)]
+pub struct LeafPlacement {
+    pub attach: f64,
+    pub rotation: Quat,
+    pub length: f64,
+    pub variation: f64,
+    // how far it's unfurled, set as the plant grows: 0 hasn't budded yet, 1 is fully open.
+    pub grown: f64,
+}
+
+impl LeafPlacement {
+    // a leaf whose tip points along `tip`, with the top of its blade facing `top` as far as it
+    // can.
+    fn pointing(attach: f64, tip: &Vec3, top: &Vec3, length: f64, variation: f64) -> Self {
+        Self {
+            attach,
+            rotation: aim(tip, &top.flipped()),
+            length,
+            variation,
+            grown: 1.,
+        }
+    }
+
+    // a simple leaf, or a compound one's rachis and leaflets. `along` is the direction its stem
+    // or branch is growing where it attaches: a leaf that's still unfurling starts out folded
+    // up against it, small, and swings out as it grows.
+    fn place(&self, origin: Vec3, along: &Vec3, compound: Option<&CompoundLeaf>, placed: &mut Placed) {
+        let id = placed.next_leaf;
+        placed.next_leaf += 1;
+        if self.grown <= 0. {
+            return;
+        }
+        let rotation = if self.grown < 1. {
+            let folded = self.rotation.then(&Quat::from_to(&self.rotation.rotate(&Vec3::up()), along));
+            folded.slerp(&self.rotation, smoothstep(self.grown))
+        } else {
+            self.rotation.clone()
+        };
+        let scale = self.length * lerpf(0.2, 1., self.grown) / LEAF_LENGTH;
+
+        let leaf = Instance::new(origin, &rotation, scale, self.variation);
+        let compound = match compound {
+            Some(compound) => compound,
+            None => {
+                placed.leaves.push(leaf);
+                placed.leaf_owners.push(id);
+                return;
             }
         };
+        for (i, (position, leaflet_rotation, size)) in compound.leaflets().into_iter().enumerate() {
+            placed.leaves.push(Instance::new(
+                leaf.to_world(&position),
+                &leaflet_rotation.then(&rotation),
+                scale * size,
+                (self.variation + variation(i + 1)).fract(),
+            ));
+            placed.leaf_owners.push(id);
+        }
+        placed.rachises.push(leaf);
+        placed.rachis_owners.push(id);
+    }
+}
 
-        Frame::new(
-            origin,
-            sidward.scale_uniform_mut(length),
-            tipward.scale_uniform_mut(length),
-            upward.scale_uniform_mut(length)
-        )
+// a pinnately compound leaf, like a rose's: `pairs` pairs of leaflets facing each other along a
+// stalk (the rachis), and one more at its tip. each leaflet is a whole simple leaf.
+#[derive(Clone, Debug)]
+pub struct CompoundLeaf {
+    pub pairs: usize,
+    // how long the leaflets are, as a fraction of the whole leaf.
+    pub leaflet_size: f64,
+    // how far the leaflets angle out from the rachis, in radians.
+    pub angle: f64,
+}
+
+impl CompoundLeaf {
+    pub fn new(pairs: usize) -> Self {
+        Self {
+            pairs,
+            leaflet_size: 0.4,
+            angle: 1.,
+        }
+    }
+
+    // the rachis runs along the simple leaf's midrib, stopping short so the terminal leaflet
+    // ends where a simple leaf would.
+    fn rachis(&self) -> RachisPrototype {
+        RachisPrototype { end: 1. - self.leaflet_size }
+    }
+
+    // each leaflet as (position, rotation, scale) in the leaf's own space, the pairs growing a
+    // little toward the tip.
+    fn leaflets(&self) -> Vec<(Vec3, Quat, f64)> {
+        let rachis = self.rachis();
+        let spine = |s: f64| rachis.spine(s);
+        let mut leaflets = vec![];
+        for pair in 0..self.pairs {
+            let t = if self.pairs > 1 { pair as f64 / (self.pairs - 1) as f64 } else { 0. };
+            let s = lerpf(0.2, 0.85, t);
+            let along = tangent(&spine, s);
+            let size = self.leaflet_size * lerpf(0.7, 0.95, t);
+            for side in &[1., -1.] {
+                let out = Quat::from_axis_angle(&Vec3::forward(), side * self.angle).rotate(&along);
+                leaflets.push((spine(s), aim(&out, &Vec3::forward()), size));
+            }
+        }
+        leaflets.push((spine(1.), aim(&tangent(&spine, 1.), &Vec3::forward()), self.leaflet_size));
+        leaflets
+    }
+}
+
+// a compound leaf's bare stalk, in the same space as a simple leaf.
+struct RachisPrototype {
+    end: f64,
+}
+
+impl RachisPrototype {
+    fn spine(&self, s: f64) -> Vec3 {
+        LeafPrototype::midrib(s * self.end)
+    }
+
+    fn thickness(s: f64) -> f64 {
+        lerpf(2.5, 1., s)
+    }
+}
+
+impl Prototype for RachisPrototype {
+    fn distance(&self, pt: &Vec3, _variation: f64) -> f64 {
+        sdf_curve(&|s| self.spine(s), &Self::thickness, pt)
+    }
+
+    fn bounds(&self) -> Aabb {
+        curve_bounds(&|s| self.spine(s), Self::thickness(0.))
+    }
+}
+
+// instances as they're gathered up from around the plant.
+#[derive(Default)]
+struct Placed {
+    leaves: Vec<Instance>,
+    rachises: Vec<Instance>,
+    sepals: Vec<Instance>,
+    // which leaf each leaf and rachis instance is part of, numbered as for `Component::Leaf`.
+    leaf_owners: Vec<usize>,
+    rachis_owners: Vec<usize>,
+    // the number of the next leaf, whether it's placed or not.
+    next_leaf: usize,
+}
+
+impl Placed {
+    // numbers the leaves on a branch that hasn't sprouted, so the ones after it keep theirs.
+    fn skip(&mut self, branch: &Branch) {
+        self.next_leaf += branch.leaves.len();
+        for child in &branch.children {
+            self.skip(child);
+        }
+    }
+}
+
+// a spread of variations that never repeats and never bunches up.
+fn variation(i: usize) -> f64 {
+    (i as f64 * 0.618_033_988_75).fract()
+}
+
+type Curve<'a> = Box<dyn Fn(f64) -> Vec3 + 'a>;
+
+// a separately editable part of the plant. branches are numbered parents before their children,
+// as in `Flower::branch_rests`, and leaves are numbered the stem's first, then each branch's in
+// the same order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Component {
+    Stem,
+    Branch(usize),
+    Leaf(usize),
+}
+
+// a point on a branch or leaf that's dragged to reshape it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Grip {
+    // where it attaches, which slides along whatever it grows from.
+    Attach,
+    // a branch's middle bezier control.
+    Middle,
+    // the end of a branch, or the tip of a leaf, which turns it.
+    Tip,
+}
+
+// the branches and leaves as they've been edited, to put back.
+#[derive(Clone, Debug)]
+pub struct Shape {
+    branches: Vec<Branch>,
+    stem_leaves: Vec<LeafPlacement>,
+    arrangement: Option<LeafArrangement>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Part {
+    Stem,
+    Leaf,
+    Petal,
+}
+
+// what the surface at a point is made of, and how far along its part's curve it is (0 at the
+// base, 1 at the tip), for coloring.
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub part: Part,
+    pub s: f64,
+}
+
+// a woody side branch: a quadratic bezier leaving its parent curve, with its own side branches
+// and leaves, and maybe a flower at its tip.
+#[derive(Clone, Debug)]
+pub struct Branch {
+    // where along the parent curve it attaches, in [0, 1].
+    pub attach: f64,
+    // the middle and tip bezier control points, relative to the attach point.
+    pub controls: [Vec3; 2],
+    // at the base; it tapers to 1 at the tip.
+    pub thickness: f64,
+    pub children: Vec<Branch>,
+    pub leaves: Vec<LeafPlacement>,
+    pub bloom: Option<Bloom>,
+    // how much of it has grown, set as the plant grows: 0 hasn't sprouted, 1 is full length.
+    pub grown: f64,
+    // how far the middle and tip controls are pushed from where they grow, by gravity and
+    // wind.
+    pub bend: [Vec3; 2],
+}
+
+impl Branch {
+    fn curve(&self, origin: &Vec3) -> impl Fn(f64) -> Vec3 {
+        let [b, c] = self.grown_controls();
+        let a = origin.clone();
+        let b = origin + &b;
+        let c = origin + &c;
+        move |s: f64| Vec3::bezier2(&a, &b, &c, s)
+    }
+
+    fn grown_controls(&self) -> [Vec3; 2] {
+        let [b, c] = self.rest_controls();
+        [&b + &self.bend[0], &c + &self.bend[1]]
+    }
+
+    // the controls as far as it's grown, before it's bent.
+    fn rest_controls(&self) -> [Vec3; 2] {
+        [&self.controls[0] * self.grown, &self.controls[1] * self.grown]
+    }
+
+    fn thickness_at(&self, s: f64) -> f64 {
+        lerpf(self.thickness * lerpf(0.5, 1., self.grown), 1., s)
+    }
+
+    fn sprouted(&self) -> bool {
+        self.grown > 0.
+    }
+
+    // a sphere around the branch's own curve (but not its children), for cheap early outs.
+    fn reach(&self, origin: &Vec3) -> (Vec3, f64) {
+        let [b, c] = self.grown_controls();
+        let hull = [origin.clone(), origin + &b, origin + &c];
+        let center = (&(&hull[0] + &hull[1]) + &hull[2]).scale_uniform_mut(1. / 3.);
+        let radius = hull.iter().map(|p| p.dist(&center)).fold(0., f64::max) + self.thickness;
+        (center, radius)
+    }
+}
+
+// the rose at the top of the stem: layers of petals curling out from the tip, with the inner
+// layers shorter and more tightly closed.
+#[derive(Clone, Debug)]
+pub struct Bloom {
+    pub petals: usize,
+    pub layers: usize,
+    // length of the outermost petals.
+    pub radius: f64,
+    // 0 is a closed bud, 1 is fully open.
+    pub openness: f64,
+    // rotation (radians) of each layer relative to the one outside it.
+    pub twist: f64,
+    // how far it's come from a bud, set as the plant grows: 0 is a small closed bud, 1 is the
+    // bloom as described above.
+    pub grown: f64,
+}
+
+impl Bloom {
+    pub fn rose(radius: f64) -> Self {
+        Self {
+            petals: 6,
+            layers: 3,
+            radius,
+            openness: 0.6,
+            twist: 0.5,
+            grown: 1.,
+        }
+    }
+
+    // each petal as (outward, direction, length): the horizontal direction it cups toward, the
+    // direction it leaves the center of the bloom in, and how long it is. `up` is the direction
+    // the stem is growing at its tip.
+    fn petal_curves(&self, up: &Vec3) -> Vec<(Vec3, Vec3, f64)> {
+        let side = {
+            let side = up.cross(&Vec3::forward());
+            if side.is_zero(0.0001) { Vec3::right() } else { side.unit() }
+        };
+
+        let mut petals = vec![];
+        for layer in 0..self.layers {
+            // 0 for the outermost layer, approaching 1 toward the center.
+            let depth = layer as f64 / self.layers as f64;
+            let openness = self.openness * smoothstep(self.grown) * (1. - depth);
+            let length = self.grown_radius() * lerpf(1., 0.45, depth) * lerpf(0.7, 1., openness);
+            for i in 0..self.petals {
+                let angle = std::f64::consts::PI * 2. * i as f64 / self.petals as f64
+                    + self.twist * layer as f64;
+                let outward = Quat::from_axis_angle(up, angle).rotate(&side);
+                let dir = Vec3::lerp(up, &outward, lerpf(0.15, 0.9, openness)).unit();
+                petals.push((outward, dir, length));
+            }
+        }
+        petals
+    }
+
+    // how far from the center of the bloom any petal reaches.
+    fn reach(&self) -> f64 {
+        self.grown_radius() + self.petal_thickness(0.5)
+    }
+
+    fn grown_radius(&self) -> f64 {
+        self.radius * lerpf(0.3, 1., self.grown)
+    }
+
+    fn petal_thickness(&self, s: f64) -> f64 {
+        // widest a little past the middle, like a petal blade.
+        lerpf(1.5, 0.5, s) + self.grown_radius() * 0.12 * (std::f64::consts::PI * s).sin()
+    }
+}
+
+// depth of the fine ridges running along the stem.
+const STEM_RIDGE_DEPTH: f64 = 0.4;
+// and how far apart they are around it.
+const STEM_RIDGE_WIDTH: f64 = 1.5;
+// the ridges make the stem's field change faster than distance does, by up to this factor.
+// dividing by it keeps the field a lower bound on the distance, so rays can't march past the
+// surface into a groove.
+const STEM_RIDGE_SLOPE: f64 = 1. + STEM_RIDGE_DEPTH * PERLIN3_SLOPE / STEM_RIDGE_WIDTH;
+
+// how sharply parts blend together where they meet.
+const BLEND: f64 = 2.;
+
+// how long each part takes to grow, relative to the main stem. parts along a stem or branch
+// start growing as it grows past them, and blooms once it's done.
+const STEM_TIME: f64 = 1.;
+const BRANCH_TIME: f64 = 0.6;
+const LEAF_TIME: f64 = 0.4;
+const THORN_TIME: f64 = 0.2;
+const BLOOM_TIME: f64 = 0.6;
+
+// how far along something is at time `t`, if it starts growing at `start` and takes `duration`.
+fn grown(t: f64, start: f64, duration: f64) -> f64 {
+    ((t - start) / duration).clamp(0., 1.)
+}
+
+// eases in and out of [0, 1].
+fn smoothstep(x: f64) -> f64 {
+    x * x * (3. - 2. * x)
+}
+
+// leaves placed around the stem by a phyllotaxis pattern, rather than by hand.
+#[derive(Clone, Debug)]
+pub struct LeafArrangement {
+    pub pattern: Phyllotaxis,
+    pub leaves: usize,
+    // the span of the stem that leaves grow along.
+    pub start: f64,
+    pub end: f64,
+    // of the lowest leaves; leaves get smaller toward the top.
+    pub length: f64,
+    // angle (radians) between the stem and each leaf.
+    pub divergence: f64,
+}
+
+impl LeafArrangement {
+    pub fn new(pattern: Phyllotaxis, leaves: usize) -> Self {
+        Self {
+            pattern,
+            leaves,
+            start: 0.15,
+            end: 0.6,
+            length: 110.,
+            divergence: 0.9,
+        }
     }
 }
 
 pub struct Flower {
     control_points: Vec<Vec3>,
-    leaf_gen: LeafGen,
+    branches: Vec<Branch>,
+    stem_leaves: Vec<LeafPlacement>,
+    // when set, `stem_leaves` are generated from it whenever the stem moves.
+    arrangement: Option<LeafArrangement>,
+    // when set, every leaf is made of leaflets along a rachis rather than one blade.
+    compound: Option<CompoundLeaf>,
+    bloom: Option<Bloom>,
+    thorns: Thorns,
+    // under every bloom, the main one and the branches'.
+    sepals: Sepals,
+    // every placed leaf, from `stem_leaves` and the branches' leaves, and every thorn and sepal,
+    // in world space.
+    leaves: Instanced<LeafPrototype>,
+    rachises: Instanced<RachisPrototype>,
+    // which leaf each instance in `leaves` and `rachises` is part of.
+    leaf_owners: Vec<usize>,
+    rachis_owners: Vec<usize>,
+    thorn_instances: Instanced<ThornPrototype>,
+    sepal_instances: Instanced<SepalPrototype>,
+    // 0 is a seedling, 1 the plant as designed.
+    growth: f64,
+    // `growth` in units of the time the main stem takes to grow.
+    time: f64,
+    turn: f64,
+    noise: Noise,
 }
 
 impl Flower {
     pub fn new() -> Self {
-        Self {
+        let thorns = Thorns::new(9, 9., 0.5);
+        let sepals = Sepals::new(5, 30., 0.5);
+        let mut flower = Self {
             control_points: vec![
-                Vec3::zero(),
-                Vec3::zero(),
-                Vec3::zero(),
-                Vec3::zero(),
+                Vec3::new(354., 591., 0.),
+                Vec3::new(395., 410., 0.),
+                Vec3::new(259., 399., 0.),
+                Vec3::new(310., 211., 0.),
             ],
-            leaf_gen: LeafGen {
-                vein_pairs: 6,
-                base_offset: 0.1,
-                margin_shape: Box::new(|s: f64| Vec3::lerp(&Vec3::zero(), &Vec3::right(), s)),
-                vein_shape: Box::new(|s: f64| Vec3::lerp(&Vec3::zero(), &Vec3::right(), s)),
-            },
+            branches: vec![
+                Branch {
+                    attach: 0.45,
+                    controls: [Vec3::new(40., -120., 0.), Vec3::new(20., -160., 0.)],
+                    thickness: 4.,
+                    children: vec![Branch {
+                        attach: 0.23,
+                        controls: [Vec3::new(70., -50., 0.), Vec3::new(50., -90., 0.)],
+                        thickness: lerpf(4., 1., 0.23),
+                        children: vec![],
+                        leaves: vec![],
+                        bloom: None,
+                        grown: 1.,
+                        bend: [Vec3::zero(), Vec3::zero()],
+                    }],
+                    leaves: vec![],
+                    bloom: None,
+                    grown: 1.,
+                    bend: [Vec3::zero(), Vec3::zero()],
+                },
+                Branch {
+                    attach: 0.55,
+                    controls: [Vec3::new(-50., -60., 0.), Vec3::new(-90., -90., 0.)],
+                    thickness: 4.,
+                    children: vec![],
+                    leaves: vec![],
+                    bloom: None,
+                    grown: 1.,
+                    bend: [Vec3::zero(), Vec3::zero()],
+                },
+            ],
+            stem_leaves: vec![LeafPlacement::pointing(
+                0.15,
+                &Vec3::new(-100., -80., 0.),
+                &Vec3::forward().flipped(),
+                120.,
+                0.,
+            )],
+            arrangement: None,
+            compound: None,
+            bloom: Some(Bloom::rose(36.)),
+            thorn_instances: Instanced::new(thorns.prototype()),
+            sepal_instances: Instanced::new(sepals.prototype()),
+            thorns,
+            sepals,
+            leaves: Instanced::new(LeafPrototype::new(6)),
+            rachises: Instanced::new(CompoundLeaf::new(0).rachis()),
+            leaf_owners: vec![],
+            rachis_owners: vec![],
+            growth: 1.,
+            time: f64::INFINITY,
+            turn: 0.,
+            noise: Noise::new(0),
+        };
+        flower.place_instances();
+        flower
+    }
+
+    // a random plant, standing on the ground of a canvas of the given size. the same seed always
+    // grows the same plant.
+    pub fn from_seed(seed: u64, width: f64, height: f64) -> Self {
+        let mut rng = Rng::new(seed);
+
+        let base = Vec3::new(width * rng.range(0.3, 0.45), height - rng.range(40., 60.), 0.);
+        let stem_height = height * rng.range(0.5, 0.72);
+        let sway = |rng: &mut Rng| rng.range(-70., 70.);
+        let control_points = vec![
+            base.clone(),
+            &base + &Vec3::new(sway(&mut rng), -stem_height * rng.range(0.25, 0.4), 0.),
+            &base + &Vec3::new(sway(&mut rng), -stem_height * rng.range(0.55, 0.75), 0.),
+            &base + &Vec3::new(sway(&mut rng) * 0.5, -stem_height, 0.),
+        ];
+
+        let pattern = [Phyllotaxis::Alternate, Phyllotaxis::Opposite, Phyllotaxis::Spiral]
+            [rng.range_usize(0, 3)];
+        let arrangement = LeafArrangement {
+            pattern,
+            leaves: rng.range_usize(2, 7),
+            start: rng.range(0.1, 0.2),
+            end: rng.range(0.5, 0.75),
+            length: stem_height * rng.range(0.18, 0.28),
+            divergence: rng.range(0.6, 1.2),
+        };
+
+        let bloom = Bloom {
+            petals: rng.range_usize(5, 10),
+            layers: rng.range_usize(2, 5),
+            radius: rng.range(28., 46.),
+            openness: rng.range(0.25, 0.95),
+            twist: rng.range(0.2, 1.2),
+            grown: 1.,
+        };
+
+        let vein_pairs = rng.range_usize(4, 10);
+        let thorns = Thorns::new(rng.range_usize(0, 12), rng.range(6., 12.), rng.range(0.1, 0.9));
+        let sepals = Sepals::new(5, bloom.radius * rng.range(0.6, 0.9), rng.range(0.2, 0.9));
+        // about half of them get rose-like compound leaves.
+        let compound = if rng.range(0., 1.) < 0.5 {
+            Some(CompoundLeaf::new(rng.range_usize(1, 4)))
+        } else {
+            None
+        };
+        let mut flower = Self {
+            control_points,
+            branches: vec![],
+            stem_leaves: vec![],
+            arrangement: None,
+            rachises: Instanced::new(compound.as_ref().unwrap_or(&CompoundLeaf::new(0)).rachis()),
+            leaf_owners: vec![],
+            rachis_owners: vec![],
+            compound,
+            bloom: Some(bloom),
+            thorn_instances: Instanced::new(thorns.prototype()),
+            sepal_instances: Instanced::new(sepals.prototype()),
+            thorns,
+            sepals,
+            leaves: Instanced::new(LeafPrototype::new(vein_pairs)),
+            growth: 1.,
+            time: f64::INFINITY,
+            turn: 0.,
+            noise: Noise::new(seed),
+        };
+        flower.set_arrangement(arrangement);
+        flower
+    }
+
+    // grows the plant an l-system describes, standing on the ground of a canvas of the given
+    // size. the main shoot becomes the stem, and every bracketed shoot a branch.
+    pub fn grow(lsystem: &LSystem, iterations: usize, width: f64, height: f64) -> Result<Self, LSystemError> {
+        let shoot = lsystem.grow(iterations)?;
+        if shoot.length() == 0. {
+            return Err(LSystemError::NoStem);
         }
+
+        let base = Vec3::new(width * 0.4, height - 50., 0.);
+        let through = |t: f64| &base + &shoot.point_at(t);
+        let control_points = fit_bezier3(&base, &through(1. / 3.), &through(2. / 3.), &through(1.));
+
+        // leaves are a few steps long, and flowers a couple of steps across.
+        let leaf_length = lsystem.step * 3.;
+        let bloom = Bloom::rose(lsystem.step * 2.);
+
+        let thorns = Thorns::new(8, lsystem.step * 0.5, 0.5);
+        let sepals = Sepals::new(5, bloom.radius * 0.8, 0.5);
+
+        let compound = CompoundLeaf::new(2);
+
+        let (branches, stem_leaves) = side_branches(&shoot, &|s| lerpf(4., 3., s), leaf_length, &bloom);
+        let mut flower = Self {
+            control_points,
+            branches,
+            stem_leaves,
+            arrangement: None,
+            rachises: Instanced::new(compound.rachis()),
+            leaf_owners: vec![],
+            rachis_owners: vec![],
+            compound: Some(compound),
+            bloom: if shoot.flower { Some(bloom) } else { None },
+            thorn_instances: Instanced::new(thorns.prototype()),
+            sepal_instances: Instanced::new(sepals.prototype()),
+            thorns,
+            sepals,
+            leaves: Instanced::new(LeafPrototype::new(6)),
+            growth: 1.,
+            time: f64::INFINITY,
+            turn: 0.,
+            noise: Noise::new(0),
+        };
+        flower.place_instances();
+        Ok(flower)
+    }
+
+    pub fn controls(&self) -> &Vec<Vec3> {
+        &self.control_points
     }
 
     pub fn update_controls(&mut self, points: &Vec<Vec3>) {
@@ -104,10 +765,539 @@ impl Flower {
         for i in 0..points.len() {
             self.control_points[i] = points[i].clone();
         }
+        self.arrange_leaves();
+        self.place_instances();
+    }
+
+    // every branch's controls as grown but unbent, relative to where it attaches, and its
+    // thickness, parents before their children.
+    pub fn branch_rests(&self) -> Vec<([Vec3; 2], f64)> {
+        fn collect(branches: &[Branch], rests: &mut Vec<([Vec3; 2], f64)>) {
+            for branch in branches {
+                rests.push((branch.rest_controls(), branch.thickness));
+                collect(&branch.children, rests);
+            }
+        }
+        let mut rests = vec![];
+        collect(&self.branches, &mut rests);
+        rests
+    }
+
+    // bends each branch, in the same order as `branch_rests`. branches without a bend are
+    // straightened back out.
+    pub fn bend_branches(&mut self, bends: &[[Vec3; 2]]) {
+        fn bend(branches: &mut [Branch], bends: &mut std::slice::Iter<[Vec3; 2]>) {
+            for branch in branches {
+                branch.bend = bends.next().cloned().unwrap_or([Vec3::zero(), Vec3::zero()]);
+                bend(&mut branch.children, bends);
+            }
+        }
+        bend(&mut self.branches, &mut bends.iter());
+        self.place_instances();
+    }
+
+    // which part of the plant is nearest `pt`, in the scene.
+    pub fn component_at(&self, pt: &Vec3) -> Component {
+        let pt = &self.to_plant(pt);
+        let mut nearest = (self.stem_distance(pt), Component::Stem);
+        let mut index = 0;
+        for branch in &self.branches {
+            self.nearest_branch(branch, &self.stem_bezier(branch.attach), pt, &mut index, &mut nearest);
+        }
+        for (instance, &leaf) in self.leaves.instances.iter().zip(&self.leaf_owners) {
+            let d = self.leaves.instance_distance(instance, pt);
+            if d < nearest.0 {
+                nearest = (d, Component::Leaf(leaf));
+            }
+        }
+        for (instance, &leaf) in self.rachises.instances.iter().zip(&self.rachis_owners) {
+            let d = self.rachises.instance_distance(instance, pt);
+            if d < nearest.0 {
+                nearest = (d, Component::Leaf(leaf));
+            }
+        }
+        nearest.1
+    }
+
+    fn nearest_branch(&self, branch: &Branch, origin: &Vec3, pt: &Vec3, index: &mut usize, nearest: &mut (f64, Component)) {
+        let this = *index;
+        *index += 1;
+        let curve = branch.curve(origin);
+        if branch.sprouted() {
+            let d = sdf_curve(&curve, &|s| branch.thickness_at(s), pt);
+            if d < nearest.0 {
+                *nearest = (d, Component::Branch(this));
+            }
+        }
+        for child in &branch.children {
+            self.nearest_branch(child, &curve(child.attach), pt, index, nearest);
+        }
+    }
+
+    // the grips that reshape a branch or leaf, in the scene. a leaf's tip is where it reaches
+    // fully grown. the stem has none; its controls reshape it.
+    pub fn grips(&self, component: Component) -> Vec<(Grip, Vec3)> {
+        let grips = match component {
+            Component::Stem => vec![],
+            Component::Branch(i) => match self.branch_paths().get(i) {
+                Some(path) => {
+                    let branch = self.branch_at(path);
+                    let origin = self.curve_at(&path[..path.len() - 1])(branch.attach);
+                    let [middle, tip] = branch.grown_controls();
+                    vec![
+                        (Grip::Attach, origin.clone()),
+                        (Grip::Middle, &origin + &middle),
+                        (Grip::Tip, &origin + &tip),
+                    ]
+                }
+                None => vec![],
+            },
+            Component::Leaf(i) => match self.leaf_paths().get(i) {
+                Some((path, j)) => {
+                    let leaf = self.leaf_at(path, *j);
+                    let origin = self.curve_at(path)(leaf.attach);
+                    let tip = &origin + &(&leaf.rotation.rotate(&Vec3::up()) * leaf.length);
+                    vec![(Grip::Attach, origin), (Grip::Tip, tip)]
+                }
+                None => vec![],
+            },
+        };
+        grips.into_iter()
+            .map(|(grip, pt)| (grip, self.to_world(&pt)))
+            .collect()
+    }
+
+    // drags a grip of a branch or leaf to `to`, in the scene. attach points slide to wherever
+    // along their parent is closest. editing a leaf on the stem places the stem's leaves by hand
+    // from then on.
+    pub fn move_grip(&mut self, component: Component, grip: Grip, to: &Vec3) {
+        let to = self.to_plant(to);
+        match component {
+            Component::Stem => return,
+            Component::Branch(i) => {
+                let path = match self.branch_paths().into_iter().nth(i) {
+                    Some(path) => path,
+                    None => return,
+                };
+                let (attach, origin) = {
+                    let parent = self.curve_at(&path[..path.len() - 1]);
+                    (find_closest_point(&to, &parent), parent(self.branch_at(&path).attach))
+                };
+                let branch = self.branch_at_mut(&path);
+                match grip {
+                    Grip::Attach => branch.attach = attach,
+                    Grip::Middle | Grip::Tip if branch.grown > 0. => {
+                        let k = if grip == Grip::Middle { 0 } else { 1 };
+                        branch.controls[k] = &(&(&to - &origin) - &branch.bend[k]) * (1. / branch.grown);
+                    }
+                    _ => (),
+                }
+            }
+            Component::Leaf(i) => {
+                let (path, j) = match self.leaf_paths().into_iter().nth(i) {
+                    Some(leaf) => leaf,
+                    None => return,
+                };
+                let (attach, origin) = {
+                    let parent = self.curve_at(&path);
+                    (find_closest_point(&to, &parent), parent(self.leaf_at(&path, j).attach))
+                };
+                if path.is_empty() {
+                    self.arrangement = None;
+                }
+                let leaf = self.leaf_at_mut(&path, j);
+                match grip {
+                    Grip::Attach => leaf.attach = attach,
+                    Grip::Tip => {
+                        let tip = &to - &origin;
+                        if tip.mag() > 0. {
+                            // the top of the blade keeps facing the same way as far as it can.
+                            leaf.rotation = aim(&tip, &leaf.rotation.rotate(&Vec3::forward()));
+                            leaf.length = tip.mag();
+                        }
+                    }
+                    Grip::Middle => (),
+                }
+            }
+        }
+        self.place_instances();
+    }
+
+    pub fn shape(&self) -> Shape {
+        Shape {
+            branches: self.branches.clone(),
+            stem_leaves: self.stem_leaves.clone(),
+            arrangement: self.arrangement.clone(),
+        }
+    }
+
+    pub fn set_shape(&mut self, shape: Shape) {
+        self.branches = shape.branches;
+        self.stem_leaves = shape.stem_leaves;
+        self.arrangement = shape.arrangement;
+        self.arrange_leaves();
+        self.place_instances();
+    }
+
+    // the path to every branch, as an index into each level of children, numbered as for
+    // `Component::Branch`.
+    fn branch_paths(&self) -> Vec<Vec<usize>> {
+        fn collect(branches: &[Branch], path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+            for (i, branch) in branches.iter().enumerate() {
+                path.push(i);
+                paths.push(path.clone());
+                collect(&branch.children, path, paths);
+                path.pop();
+            }
+        }
+        let mut paths = vec![];
+        collect(&self.branches, &mut vec![], &mut paths);
+        paths
+    }
+
+    // the path to the branch each leaf grows on (empty for the stem) and which of its leaves it
+    // is, numbered as for `Component::Leaf`.
+    fn leaf_paths(&self) -> Vec<(Vec<usize>, usize)> {
+        let mut paths: Vec<(Vec<usize>, usize)> = (0..self.stem_leaves.len()).map(|j| (vec![], j)).collect();
+        for path in self.branch_paths() {
+            let count = self.branch_at(&path).leaves.len();
+            paths.extend((0..count).map(|j| (path.clone(), j)));
+        }
+        paths
+    }
+
+    fn branch_at(&self, path: &[usize]) -> &Branch {
+        let mut branch = &self.branches[path[0]];
+        for &i in &path[1..] {
+            branch = &branch.children[i];
+        }
+        branch
+    }
+
+    fn branch_at_mut(&mut self, path: &[usize]) -> &mut Branch {
+        let mut branch = &mut self.branches[path[0]];
+        for &i in &path[1..] {
+            branch = &mut branch.children[i];
+        }
+        branch
+    }
+
+    fn leaf_at(&self, path: &[usize], j: usize) -> &LeafPlacement {
+        if path.is_empty() { &self.stem_leaves[j] } else { &self.branch_at(path).leaves[j] }
+    }
+
+    fn leaf_at_mut(&mut self, path: &[usize], j: usize) -> &mut LeafPlacement {
+        if path.is_empty() { &mut self.stem_leaves[j] } else { &mut self.branch_at_mut(path).leaves[j] }
+    }
+
+    // the curve of the branch at `path`, or the stem for an empty path.
+    fn curve_at(&self, path: &[usize]) -> Curve<'_> {
+        let mut curve: Curve<'_> = Box::new(move |s| self.stem_bezier(s));
+        let mut branches = &self.branches;
+        for &i in path {
+            let branch = &branches[i];
+            curve = Box::new(branch.curve(&curve(branch.attach)));
+            branches = &branch.children;
+        }
+        curve
+    }
+
+    pub fn arrangement(&self) -> Option<&LeafArrangement> {
+        self.arrangement.as_ref()
+    }
+
+    // replaces the plant's branches with leaves arranged around the stem.
+    pub fn set_arrangement(&mut self, arrangement: LeafArrangement) {
+        self.arrangement = Some(arrangement);
+        self.branches.clear();
+        self.arrange_leaves();
+        self.place_instances();
+    }
+
+    pub fn compound_leaves(&self) -> Option<&CompoundLeaf> {
+        self.compound.as_ref()
+    }
+
+    // makes every leaf compound, or simple again with none.
+    pub fn set_compound_leaves(&mut self, compound: Option<CompoundLeaf>) {
+        if let Some(compound) = &compound {
+            self.rachises = Instanced::new(compound.rachis());
+        }
+        self.compound = compound;
+        self.place_instances();
+    }
+
+    // shows the plant part way through growing from a seedling: the stem lengthens, branches
+    // sprout and leaves unfurl as it passes them, and buds open into blooms at the end.
+    pub fn set_growth(&mut self, growth: f64) {
+        self.growth = growth.clamp(0., 1.);
+        self.place_instances();
+    }
+
+    // sets how far each part has grown, from `growth`.
+    fn apply_growth(&mut self) {
+        // running the schedule to the end finds how long it takes, so growth 1 is the whole
+        // plant.
+        let total = self.schedule(f64::INFINITY);
+        self.time = self.growth * total;
+        self.schedule(self.time);
+    }
+
+    // grows every part to time `t`, returning when the last part finishes.
+    fn schedule(&mut self, t: f64) -> f64 {
+        let mut end = STEM_TIME;
+        for leaf in &mut self.stem_leaves {
+            leaf.grown = grown(t, leaf.attach * STEM_TIME, LEAF_TIME);
+            end = end.max(leaf.attach * STEM_TIME + LEAF_TIME);
+        }
+        for branch in &mut self.branches {
+            end = end.max(schedule_branch(branch, branch.attach * STEM_TIME, t));
+        }
+        if let Some(bloom) = &mut self.bloom {
+            bloom.grown = grown(t, STEM_TIME, BLOOM_TIME);
+            end = end.max(STEM_TIME + BLOOM_TIME);
+        }
+        end
+    }
+
+    pub fn thorns(&self) -> &Thorns {
+        &self.thorns
+    }
+
+    pub fn set_thorns(&mut self, thorns: Thorns) {
+        self.thorn_instances = Instanced::new(thorns.prototype());
+        self.thorns = thorns;
+        self.place_instances();
+    }
+
+    pub fn set_sepals(&mut self, sepals: Sepals) {
+        self.sepal_instances = Instanced::new(sepals.prototype());
+        self.sepals = sepals;
+        self.place_instances();
+    }
+
+    fn arrange_leaves(&mut self) {
+        let arrangement = match &self.arrangement {
+            Some(arrangement) => arrangement,
+            None => return,
+        };
+        let placements = arrangement.pattern.placements(arrangement.leaves, arrangement.start, arrangement.end);
+        let count = placements.len();
+        let frames = self.stem_frames();
+        self.stem_leaves = placements.into_iter()
+            .enumerate()
+            .map(|(i, (s, azimuth))| {
+                let t = if count > 1 { i as f64 / (count - 1) as f64 } else { 0. };
+                let length = arrangement.length * lerpf(1., 0.6, t);
+                let frame = frames.at(s);
+                // lean away from the stem (toward its binormal), then spin around it. the top
+                // of the blade faces up the stem.
+                let spin = Quat::from_axis_angle(&frame.tangent, -azimuth);
+                let tip = Quat::from_axis_angle(&frame.normal, -arrangement.divergence)
+                    .then(&spin)
+                    .rotate(&frame.tangent);
+                LeafPlacement::pointing(s, &tip, &frame.tangent, length, variation(i))
+            })
+            .collect();
+    }
+
+    // rebuilds the leaf, thorn and sepal instances from where they sit on the stem and branches
+    // now.
+    fn place_instances(&mut self) {
+        self.apply_growth();
+
+        let mut placed = Placed::default();
+        let stem = |s| self.stem_bezier(s);
+        for leaf in &self.stem_leaves {
+            leaf.place(stem(leaf.attach), &tangent(&stem, leaf.attach), self.compound.as_ref(), &mut placed);
+        }
+        for branch in &self.branches {
+            if branch.sprouted() {
+                self.place_on_branch(branch, &stem(branch.attach), &mut placed);
+            } else {
+                placed.skip(branch);
+            }
+        }
+        if let Some(bloom) = &self.bloom {
+            let (tip, up) = tip_of(&stem);
+            place_sepals(&self.sepals, bloom.grown, &tip, &up, &mut placed.sepals);
+        }
+
+        let frames = self.stem_frames();
+        let thorns = self.thorns.placements()
+            .into_iter()
+            .filter_map(|(s, angle, variation)| {
+                let grown = grown(self.time, s * STEM_TIME, THORN_TIME);
+                if grown <= 0. {
+                    return None;
+                }
+                let frame = frames.at(s);
+                let outward = Quat::from_axis_angle(&frame.tangent, angle).rotate(&frame.normal);
+                let base = &frame.position + &(&outward * (self.stem_thickness(s) * (1. - THORN_DEPTH)));
+                // with +x up the stem, the hook toward -x points back down it.
+                let rotation = aim(&outward, &frame.tangent.cross(&outward));
+                Some(Instance::new(base, &rotation, self.thorns.scale() * grown, variation))
+            })
+            .collect();
+
+        self.leaves.instances = placed.leaves;
+        self.rachises.instances = placed.rachises;
+        self.leaf_owners = placed.leaf_owners;
+        self.rachis_owners = placed.rachis_owners;
+        self.thorn_instances.instances = thorns;
+        self.sepal_instances.instances = placed.sepals;
+    }
+
+    fn place_on_branch(&self, branch: &Branch, origin: &Vec3, placed: &mut Placed) {
+        let curve = branch.curve(origin);
+        for leaf in &branch.leaves {
+            leaf.place(curve(leaf.attach), &tangent(&curve, leaf.attach), self.compound.as_ref(), placed);
+        }
+        for child in &branch.children {
+            if child.sprouted() {
+                self.place_on_branch(child, &curve(child.attach), placed);
+            } else {
+                placed.skip(child);
+            }
+        }
+        if let Some(bloom) = &branch.bloom {
+            let (tip, up) = tip_of(&curve);
+            place_sepals(&self.sepals, bloom.grown, &tip, &up, &mut placed.sepals);
+        }
+    }
+
+    // frames along the stem, starting with the normal toward the viewer: the binormal is then
+    // across the stem in the picture plane, and stays on the same side as the stem bends.
+    fn stem_frames(&self) -> CurveFrames {
+        CurveFrames::rotation_minimizing(&|s| self.stem_bezier(s), 0., 1., 32, &Vec3::forward())
     }
 
     pub fn distance(&self, point: &Vec3) -> f64 {
-        self.vascular_sdf(point)
+        self.vascular_sdf(&self.to_plant(point))
+    }
+
+    pub fn material(&self, pt: &Vec3) -> Material {
+        let pt = &self.to_plant(pt);
+        let mut nearest = (
+            self.stem_distance(pt),
+            Part::Stem,
+            Box::new(move |s| self.stem_bezier(s)) as Curve<'_>,
+        );
+        for branch in self.branches.iter().filter(|b| b.sprouted()) {
+            self.nearest_branch_curve(branch, &self.stem_bezier(branch.attach), pt, &mut nearest);
+        }
+        if let Some((instance, d)) = self.leaves.nearest(pt) {
+            if d < nearest.0 {
+                nearest = (d, Part::Leaf, Box::new(move |s| instance.to_world(&LeafPrototype::midrib(s))));
+            }
+        }
+        if let Some((instance, d)) = self.rachises.nearest(pt) {
+            if d < nearest.0 {
+                let rachis = &self.rachises.prototype;
+                nearest = (d, Part::Leaf, Box::new(move |s| instance.to_world(&rachis.spine(s))));
+            }
+        }
+        if let Some((instance, d)) = self.thorn_instances.nearest(pt) {
+            if d < nearest.0 {
+                let spine = self.thorn_instances.prototype.spine(instance.variation);
+                nearest = (d, Part::Stem, Box::new(move |s| instance.to_world(&spine(s))));
+            }
+        }
+        if let Some((instance, d)) = self.sepal_instances.nearest(pt) {
+            if d < nearest.0 {
+                let spine = self.sepal_instances.prototype.spine(instance.variation);
+                nearest = (d, Part::Leaf, Box::new(move |s| instance.to_world(&spine(s))));
+            }
+        }
+        if let Some(bloom) = &self.bloom {
+            let (tip, up) = tip_of(&|s| self.stem_bezier(s));
+            nearest_petal_curve(bloom, &tip, &up, pt, &mut nearest);
+        }
+
+        let (_, part, curve) = nearest;
+        Material {
+            part,
+            s: find_closest_point(pt, curve),
+        }
+    }
+
+    fn nearest_branch_curve<'a>(&self, branch: &'a Branch, origin: &Vec3, pt: &Vec3, nearest: &mut (f64, Part, Curve<'a>)) {
+        let curve = branch.curve(origin);
+        let d = sdf_curve(&curve, &|s| branch.thickness_at(s), pt);
+        for child in branch.children.iter().filter(|b| b.sprouted()) {
+            self.nearest_branch_curve(child, &curve(child.attach), pt, nearest);
+        }
+        if let Some(bloom) = &branch.bloom {
+            let (tip, up) = tip_of(&curve);
+            nearest_petal_curve(bloom, &tip, &up, pt, nearest);
+        }
+        if d < nearest.0 {
+            *nearest = (d, Part::Stem, Box::new(curve));
+        }
+    }
+
+    // bounding boxes of each separately-editable part of the plant: the stem, then each branch
+    // in the order they're attached, then each placed leaf (or leaflet), rachis, thorn and sepal,
+    // then the bloom. parts blend together with `exp_smin`, so the boxes are padded by a little
+    // more than the blend radius.
+    pub fn bounds(&self) -> Vec<Aabb> {
+        let mut bounds = vec![curve_bounds(&|s| self.stem_bezier(s), 5. + STEM_RIDGE_DEPTH)];
+        for branch in &self.branches {
+            bounds.push(self.branch_bounds(branch, &self.stem_bezier(branch.attach)));
+        }
+        bounds.extend(self.leaves.bounds());
+        bounds.extend(self.rachises.bounds());
+        bounds.extend(self.thorn_instances.bounds());
+        bounds.extend(self.sepal_instances.bounds());
+        if let Some(bloom) = &self.bloom {
+            let (tip, _) = tip_of(&|s| self.stem_bezier(s));
+            bounds.push(Aabb::from_point(&tip).expand(bloom.reach()));
+        }
+        bounds.into_iter()
+            .map(|b| b.transformed(|p| self.to_world(p)).expand(1.))
+            .collect()
+    }
+
+    // turns the whole plant `angle` radians around the vertical through the base of its stem,
+    // as if on a turntable.
+    pub fn set_turn(&mut self, angle: f64) {
+        self.turn = angle;
+    }
+
+    // from the scene into the plant's own unturned space, and back.
+    fn to_plant(&self, pt: &Vec3) -> Vec3 {
+        self.turned(pt, -self.turn)
+    }
+
+    fn to_world(&self, pt: &Vec3) -> Vec3 {
+        self.turned(pt, self.turn)
+    }
+
+    fn turned(&self, pt: &Vec3, angle: f64) -> Vec3 {
+        if angle == 0. {
+            return pt.clone();
+        }
+        let pivot = &self.control_points[0];
+        pivot + &Quat::from_axis_angle(&Vec3::up(), angle).rotate(&(pt - pivot))
+    }
+
+    fn branch_bounds(&self, branch: &Branch, origin: &Vec3) -> Aabb {
+        let curve = branch.curve(origin);
+        let mut bounds = curve_bounds(&curve, branch.thickness);
+        for child in &branch.children {
+            bounds = bounds.union(&self.branch_bounds(child, &curve(child.attach)));
+        }
+        if let Some(bloom) = &branch.bloom {
+            bounds = bounds.union(&Aabb::from_point(&curve(1.)).expand(bloom.reach()));
+        }
+        bounds
+    }
+
+    // noise stretched far along the y axis, which is roughly the direction the stem runs, so it
+    // reads as grooves rather than bumps.
+    fn stem_ridges(&self, pt: &Vec3) -> f64 {
+        let pt = Vec3::new(pt.x / STEM_RIDGE_WIDTH, pt.y / 25., pt.z / STEM_RIDGE_WIDTH);
+        STEM_RIDGE_DEPTH * self.noise.perlin3(&pt)
     }
 
     fn stem_thickness(&self, s: f64) -> f64 {
@@ -118,93 +1308,418 @@ impl Flower {
         )
     }
 
-    fn vascular_sdf(&self, pt: &Vec3) -> f64 {
-        let mut distances: Vec<f64> = vec![];
-
-        // stem
-        distances.push(sdf_curve(
+    fn stem_distance(&self, pt: &Vec3) -> f64 {
+        (sdf_curve(
             &|s| self.stem_bezier(s),
             &|s| self.stem_thickness(s),
-            pt));
+            pt) + self.stem_ridges(pt)) / STEM_RIDGE_SLOPE
+    }
 
-        distances.push(self.bottom_leaf(pt));
-        distances.push(self.top_leaf(pt));
-        distances.push(self.middle_leaf(pt));
+    fn vascular_sdf(&self, pt: &Vec3) -> f64 {
+        let mut distances: Vec<f64> = vec![self.stem_distance(pt)];
+
+        for branch in self.branches.iter().filter(|b| b.sprouted()) {
+            distances.push(self.branch_distance(branch, &self.stem_bezier(branch.attach), pt));
+        }
+
+        for d in &[
+            self.leaves.distance(pt, BLEND),
+            self.rachises.distance(pt, BLEND),
+            self.thorn_instances.distance(pt, BLEND),
+            self.sepal_instances.distance(pt, BLEND),
+        ] {
+            distances.extend(d);
+        }
+
+        if let Some(bloom) = &self.bloom {
+            let (tip, up) = tip_of(&|s| self.stem_bezier(s));
+            distances.push(bloom_distance(bloom, &tip, &up, pt));
+        }
 
         let mut sd: Option<f64> = None;
         for d in distances {
             sd = Some(match sd {
                 None => d,
-                Some(sd) => exp_smin(sd, d, 2.),
+                Some(sd) => exp_smin(sd, d, BLEND),
             });
         }
         sd.unwrap()
     }
 
-    fn leaf<C: Fn(f64) -> (Vec3, f64)>(&self, midrib: &C, pt: &Vec3) -> f64 {
-        self.leaf_gen.vein_distance(&midrib, pt)
+    fn branch_distance(&self, branch: &Branch, origin: &Vec3, pt: &Vec3) -> f64 {
+        let curve = branch.curve(origin);
+        // cheap early out for the branch itself, since bushes have a lot of them.
+        let (center, radius) = branch.reach(origin);
+        let to_center = center.dist(pt);
+        let mut d = if to_center > radius * 1.1 {
+            to_center - radius
+        } else {
+            sdf_curve(&curve, &|s| branch.thickness_at(s), pt)
+        };
+        for child in branch.children.iter().filter(|b| b.sprouted()) {
+            d = exp_smin(d, self.branch_distance(child, &curve(child.attach), pt), BLEND);
+        }
+        if let Some(bloom) = &branch.bloom {
+            let (tip, up) = tip_of(&curve);
+            d = exp_smin(d, bloom_distance(bloom, &tip, &up, pt), BLEND);
+        }
+        d
     }
 
-    fn bottom_leaf(&self, pt: &Vec3) -> f64 {
-        let branch_pt = self.stem_bezier(0.15);
-        let midrib = |s: f64| Vec3::bezier2(
-            &branch_pt,
-            &(&branch_pt + &Vec3::new(-50., -60., 0.)),
-            &(&branch_pt + &Vec3::new(-100., -80., 0.)),
-            s,
-        );
-        exp_smin(
-            sdf_curve(&midrib, &|s| lerpf(4., 1., s), pt),
-            self.leaf_gen.vein_distance(&|s| (midrib(s), 0.), pt),
-            2.,
+    // the stem as far as it's grown: a seedling starts out a tenth of its full height.
+    // while growing, the stem is the lower part of its full curve and `s` is a fraction of the
+    // grown part, so everything attached along it rides up as it lengthens, the way a real
+    // stem's internodes stretch. that's intended: parts keep their fraction of the way up.
+    fn stem_bezier(&self, s: f64) -> Vec3 {
+        let extent = lerpf(0.1, 1., grown(self.time, 0., STEM_TIME));
+        Vec3::bezier3(
+            &self.control_points[0],
+            &self.control_points[1],
+            &self.control_points[2],
+            &self.control_points[3],
+            s * extent,
         )
     }
+}
 
-    fn top_leaf(&self, pt: &Vec3) -> f64 {
-        let branch_pt = self.stem_bezier(0.55);
-        sdf_curve(&|s| Vec3::bezier2(
-            &branch_pt,
-            &(&branch_pt + &Vec3::new(-50., -60., 0.)),
-            &(&branch_pt + &Vec3::new(-90., -90., 0.)),
-            s,
-        ), &|s| lerpf(4., 1., s), pt)
+// grows a branch and everything on it to time `t`, given when it starts growing, returning when
+// the last of it finishes.
+fn schedule_branch(branch: &mut Branch, start: f64, t: f64) -> f64 {
+    branch.grown = grown(t, start, BRANCH_TIME);
+    let mut end = start + BRANCH_TIME;
+    for leaf in &mut branch.leaves {
+        let leaf_start = start + leaf.attach * BRANCH_TIME;
+        leaf.grown = grown(t, leaf_start, LEAF_TIME);
+        end = end.max(leaf_start + LEAF_TIME);
+    }
+    for child in &mut branch.children {
+        end = end.max(schedule_branch(child, start + child.attach * BRANCH_TIME, t));
+    }
+    if let Some(bloom) = &mut branch.bloom {
+        bloom.grown = grown(t, start + BRANCH_TIME, BLOOM_TIME);
+        end = end.max(start + BRANCH_TIME + BLOOM_TIME);
     }
+    end
+}
 
-    fn middle_leaf(&self, pt: &Vec3) -> f64 {
-        let branch_pt = self.stem_bezier(0.45);
-        let stem_pos = |s: f64| Vec3::bezier2(
-            &branch_pt,
-            &(&branch_pt + &Vec3::new(40., -120., 0.)),
-            &(&branch_pt + &Vec3::new(20., -160., 0.)),
-            s,
+// where a curve ends, and the direction it's growing there.
+fn tip_of<C: Fn(f64) -> Vec3>(curve: &C) -> (Vec3, Vec3) {
+    let tip = curve(1.);
+    let up = (&tip - &curve(0.98)).unit();
+    (tip, up)
+}
+
+fn bloom_distance(bloom: &Bloom, tip: &Vec3, up: &Vec3, pt: &Vec3) -> f64 {
+    // cheap early out, since the bloom is a lot of curves.
+    let reach = bloom.reach();
+    let to_tip = tip.dist(pt);
+    if to_tip > reach * 1.1 {
+        return to_tip - reach;
+    }
+
+    bloom.petal_curves(up)
+        .into_iter()
+        .map(|(outward, dir, length)| sdf_curve(
+            &petal_curve(tip, &outward, &dir, length),
+            &|s| bloom.petal_thickness(s),
+            pt,
+        ))
+        .fold(None as Option<f64>, |a, d| Some(match a {
+            None => d,
+            Some(a) => exp_smin(a, d, BLEND),
+        }))
+        .unwrap_or(to_tip)
+}
+
+fn nearest_petal_curve<'a>(bloom: &'a Bloom, tip: &Vec3, up: &Vec3, pt: &Vec3, nearest: &mut (f64, Part, Curve<'a>)) {
+    if tip.dist(pt) - bloom.reach() > nearest.0 {
+        return;
+    }
+    for (outward, dir, length) in bloom.petal_curves(up) {
+        let curve = petal_curve(tip, &outward, &dir, length);
+        let d = sdf_curve(&curve, &|s| bloom.petal_thickness(s), pt);
+        if d < nearest.0 {
+            *nearest = (d, Part::Petal, Box::new(curve));
+        }
+    }
+}
+
+// a petal leaving the center of the bloom in `dir`, cupping outward.
+fn petal_curve(center: &Vec3, outward: &Vec3, dir: &Vec3, length: f64) -> impl Fn(f64) -> Vec3 {
+    let a = center.clone();
+    let b = center.clone().sadd_vec_mut(length * 0.6, dir);
+    let c = b.clone().sadd_vec_mut(length * 0.45, outward);
+    move |s: f64| Vec3::bezier2(&a, &b, &c, s)
+}
+
+// a cubic bezier through `a` and `d` that also passes through `b` a third of the way along and
+// `c` two thirds of the way.
+pub fn fit_bezier3(a: &Vec3, b: &Vec3, c: &Vec3, d: &Vec3) -> Vec<Vec3> {
+    let q1 = &(b * 27.) - &(&(a * 8.) + d);
+    let q2 = &(c * 27.) - &(a + &(d * 8.));
+    vec![
+        a.clone(),
+        (&(&q1 * 2.) - &q2).scale_uniform_mut(1. / 18.),
+        (&(&q2 * 2.) - &q1).scale_uniform_mut(1. / 18.),
+        d.clone(),
+    ]
+}
+
+// the branches and leaves growing off a shoot: a branch for each side shoot, with a quadratic
+// bezier through the shoot's midpoint standing in for its path, and a leaf facing the viewer
+// for each leaf. `thickness` is the parent's thickness along its length, since a branch can't
+// be thicker than where it leaves.
+fn side_branches(
+    shoot: &Shoot,
+    thickness: &dyn Fn(f64) -> f64,
+    leaf_length: f64,
+    bloom: &Bloom) -> (Vec<Branch>, Vec<LeafPlacement>) {
+    let toward_viewer = Vec3::new(0., 0., -1.);
+    let mut branches = vec![];
+    let mut leaves: Vec<LeafPlacement> = shoot.leaves.iter()
+        .enumerate()
+        .map(|(i, (attach, dir))| LeafPlacement::pointing(*attach, dir, &toward_viewer, leaf_length, variation(i)))
+        .collect();
+    for (attach, side) in &shoot.branches {
+        if side.length() == 0. {
+            // nothing grew along it, so whatever's on it sits where it attaches.
+            let at = thickness(*attach);
+            let (side_branches, side_leaves) = side_branches(side, &|_| at, leaf_length, bloom);
+            for mut branch in side_branches {
+                branch.attach = *attach;
+                branches.push(branch);
+            }
+            for mut leaf in side_leaves {
+                leaf.attach = *attach;
+                leaf.variation = variation(leaves.len());
+                leaves.push(leaf);
+            }
+            continue;
+        }
+        let tip = side.tip();
+        let base = (thickness(*attach) * 0.8).max(1.5);
+        let (children, side_leaves) = side_branches(side, &|s| lerpf(base, 1., s), leaf_length, bloom);
+        branches.push(Branch {
+            attach: *attach,
+            controls: [&(&side.point_at(0.5) * 2.) - &(&tip * 0.5), tip],
+            thickness: base,
+            children,
+            leaves: side_leaves,
+            bloom: if side.flower { Some(bloom.clone()) } else { None },
+            grown: 1.,
+            bend: [Vec3::zero(), Vec3::zero()],
+        });
+    }
+    (branches, leaves)
+}
+
+// sepals spread evenly around the base of a bloom at `tip`, leaning out and down from `up`.
+// they start out small, wrapped around the bud, and grow as it opens.
+fn place_sepals(sepals: &Sepals, grown: f64, tip: &Vec3, up: &Vec3, instances: &mut Vec<Instance>) {
+    let across = perpendicular(up, &Vec3::right());
+    for (i, angle) in sepals.angles().into_iter().enumerate() {
+        let outward = Quat::from_axis_angle(up, angle).rotate(&across);
+        let axis = up.cross(&outward);
+        let dir = Quat::from_axis_angle(&axis, sepals.lean()).rotate(up);
+        // curling back is carrying on turning the way it leans.
+        let rotation = aim(&dir, &axis.cross(&dir));
+        let scale = sepals.scale() * lerpf(0.4, 1., grown);
+        instances.push(Instance::new(tip.clone(), &rotation, scale, variation(i)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flower::*;
+
+    #[test]
+    fn seeds_are_reproducible() {
+        let describe = |flower: &Flower| format!(
+            "{:?} {:?} {:?} {:?} {}",
+            flower.control_points,
+            flower.branches,
+            flower.arrangement,
+            flower.bloom,
+            flower.leaves.prototype.leaf_gen.vein_pairs,
         );
-        let stem_thickness = |s: f64| lerpf(4., 1., s);
-
-        let branch_s = 0.23;
-
-        exp_smin(
-            sdf_curve(&stem_pos, &stem_thickness, pt),
-            sdf_curve(
-                &|s: f64| &stem_pos(branch_s) + &Vec3::bezier2(
-                    &Vec3::zero(),
-                    &Vec3::new(70., -50., 0.),
-                    &Vec3::new(50., -90., 0.),
-                    s,
-                ),
-                &|s: f64| lerpf(stem_thickness(branch_s), 1., s),
-                pt,
-            ),
-            2.,
-        )
+        for seed in 0..8 {
+            let plant = describe(&Flower::from_seed(seed, 800., 800.));
+            assert_eq!(plant, describe(&Flower::from_seed(seed, 800., 800.)));
+            assert_ne!(plant, describe(&Flower::from_seed(seed + 1, 800., 800.)));
+        }
     }
 
-    fn stem_bezier(&self, s: f64) -> Vec3 {
-        Vec3::bezier3(
-            &self.control_points[0],
-            &self.control_points[1],
-            &self.control_points[2],
-            &self.control_points[3],
-            s,
-        )
+    #[test]
+    fn woody_branches_are_stem() {
+        let flower = Flower::new();
+        let middle = |branch: &Branch| branch.curve(&flower.stem_bezier(branch.attach))(0.5);
+        for branch in &flower.branches {
+            assert_eq!(Part::Stem, flower.material(&middle(branch)).part);
+        }
+        // the leaf low on the stem is an instance of the leaf prototype.
+        let leaf = &flower.leaves.instances[0];
+        assert_eq!(Part::Leaf, flower.material(&leaf.to_world(&LeafPrototype::midrib(0.5))).part);
+        let thorn = &flower.thorn_instances.instances[0];
+        let pt = thorn.to_world(&flower.thorn_instances.prototype.spine(thorn.variation)(0.5));
+        assert_eq!(Part::Stem, flower.material(&pt).part);
+    }
+
+    // a flower with one leaf halfway up the stem, sticking out to the right.
+    fn with_stem_leaf() -> Flower {
+        let mut flower = Flower::new();
+        flower.stem_leaves = vec![LeafPlacement::pointing(0.5, &Vec3::right(), &Vec3::forward().flipped(), 80., 0.)];
+        flower.place_instances();
+        flower
+    }
+
+    #[test]
+    fn instanced_leaves_are_solid() {
+        let flower = with_stem_leaf();
+        let leaf = &flower.leaves.instances[0];
+        for s in &[0.2, 0.5, 0.8] {
+            let pt = leaf.to_world(&LeafPrototype::midrib(*s));
+            assert!(flower.distance(&pt) < 0., "{} is outside", pt);
+        }
+        // well past its tip is empty space.
+        let beyond = leaf.to_world(&Vec3::new(0., LEAF_LENGTH * 2., 0.));
+        assert!(flower.distance(&beyond) > 0.);
+    }
+
+    #[test]
+    fn thorns_and_sepals_are_solid() {
+        let flower = Flower::new();
+        assert!(!flower.thorn_instances.instances.is_empty());
+        for thorn in &flower.thorn_instances.instances {
+            let pt = thorn.to_world(&flower.thorn_instances.prototype.spine(thorn.variation)(0.5));
+            assert!(flower.thorn_instances.instance_distance(thorn, &pt) < 0., "{} is outside", pt);
+            assert!(flower.distance(&pt) < 0.);
+        }
+        assert_eq!(5, flower.sepal_instances.instances.len());
+        for sepal in &flower.sepal_instances.instances {
+            let pt = sepal.to_world(&flower.sepal_instances.prototype.spine(sepal.variation)(0.3));
+            assert!(flower.sepal_instances.instance_distance(sepal, &pt) < 0., "{} is outside", pt);
+            assert!(flower.distance(&pt) < 0.);
+        }
+    }
+
+    #[test]
+    fn leaflets_in_opposite_pairs() {
+        let compound = CompoundLeaf::new(3);
+        let leaflets = compound.leaflets();
+        assert_eq!(7, leaflets.len());
+        for pair in leaflets[..6].chunks(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            assert!(a.0.dist(&b.0) < 1e-9 && a.2 == b.2);
+            // mirror images across the rachis.
+            let (tip_a, tip_b) = (a.1.rotate(&Vec3::up()), b.1.rotate(&Vec3::up()));
+            assert!((tip_a.x + tip_b.x).abs() < 1e-9 && tip_a.x < 0.);
+        }
+        // the terminal leaflet ends where a simple leaf would.
+        let (base, rotation, size) = &leaflets[6];
+        let end = base + &(&rotation.rotate(&Vec3::up()) * (size * LEAF_LENGTH));
+        assert!(end.dist(&LeafPrototype::midrib(1.)) < LEAF_LENGTH * 0.05, "{}", end);
+    }
+
+    #[test]
+    fn compound_leaves_are_solid() {
+        let mut flower = Flower::new();
+        // the default plant's one simple leaf becomes a compound one.
+        assert_eq!(1, flower.leaves.instances.len());
+        flower.set_compound_leaves(Some(CompoundLeaf::new(2)));
+        // two pairs and a terminal leaflet, all on the one rachis.
+        assert_eq!(5, flower.leaves.instances.len());
+        assert_eq!(1, flower.rachises.instances.len());
+        for leaflet in &flower.leaves.instances {
+            let pt = leaflet.to_world(&LeafPrototype::midrib(0.5));
+            assert!(flower.leaves.instance_distance(leaflet, &pt) < 0., "{} is outside", pt);
+            assert!(flower.distance(&pt) < 0.);
+        }
+        let rachis = &flower.rachises.instances[0];
+        let pt = rachis.to_world(&flower.rachises.prototype.spine(0.5));
+        assert!(flower.rachises.instance_distance(rachis, &pt) < 0.);
+    }
+
+    #[test]
+    fn grows_from_seedling_to_full() {
+        let mut flower = Flower::new();
+        let full_tip = flower.stem_bezier(1.);
+
+        flower.set_growth(0.);
+        assert!(flower.branches.iter().all(|b| !b.sprouted()));
+        assert!(flower.bloom.as_ref().unwrap().grown == 0.);
+        assert!(flower.stem_bezier(1.).dist(&full_tip) > 100.);
+
+        flower.set_growth(0.5);
+        let grown: Vec<f64> = flower.branches.iter().map(|b| b.grown).collect();
+        // the lowest branch sprouts first.
+        assert!(grown[0] > grown[1], "{:?}", grown);
+
+        flower.set_growth(1.);
+        assert!(flower.stem_bezier(1.).dist(&full_tip) < 1e-9);
+        assert!(flower.branches.iter().all(|b| b.grown == 1. && b.children.iter().all(|c| c.grown == 1.)));
+        assert_eq!(1., flower.bloom.as_ref().unwrap().grown);
+    }
+
+    #[test]
+    fn reshapes_branches() {
+        let mut flower = Flower::new();
+        // the first branch's child is numbered right after it.
+        let grips = flower.grips(Component::Branch(1));
+        assert_eq!(vec![Grip::Attach, Grip::Middle, Grip::Tip], grips.iter().map(|(g, _)| *g).collect::<Vec<_>>());
+        let origin = flower.curve_at(&[0])(0.23);
+        assert!(grips[0].1.dist(&origin) < 1e-9);
+
+        flower.move_grip(Component::Branch(1), Grip::Tip, &(&origin + &Vec3::new(10., -100., 0.)));
+        assert!(flower.branches[0].children[0].controls[1].dist(&Vec3::new(10., -100., 0.)) < 1e-9);
+        flower.move_grip(Component::Branch(0), Grip::Attach, &flower.stem_bezier(0.3));
+        assert!((flower.branches[0].attach - 0.3).abs() < 0.002);
+
+        assert!(flower.grips(Component::Stem).is_empty());
+        assert!(flower.grips(Component::Branch(3)).is_empty());
+    }
+
+    #[test]
+    fn picks_components() {
+        let mut flower = with_stem_leaf();
+        flower.branches[1].leaves = vec![LeafPlacement::pointing(0.5, &Vec3::right(), &Vec3::forward().flipped(), 50., 0.)];
+        flower.place_instances();
+
+        assert_eq!(Component::Stem, flower.component_at(&flower.stem_bezier(0.8)));
+        let branch = flower.curve_at(&[0])(0.7);
+        assert_eq!(Component::Branch(0), flower.component_at(&branch));
+        // the first branch's child, then the second branch.
+        let child = flower.curve_at(&[0, 0])(0.7);
+        assert_eq!(Component::Branch(1), flower.component_at(&child));
+        let second = flower.curve_at(&[1])(0.3);
+        assert_eq!(Component::Branch(2), flower.component_at(&second));
+
+        let leaves = &flower.leaves.instances;
+        assert_eq!(Component::Leaf(0), flower.component_at(&leaves[0].to_world(&LeafPrototype::midrib(0.7))));
+        assert_eq!(Component::Leaf(1), flower.component_at(&leaves[1].to_world(&LeafPrototype::midrib(0.7))));
+    }
+
+    #[test]
+    fn turns_leaves() {
+        let mut flower = Flower::new();
+        let leaf = LeafPlacement::pointing(0.5, &Vec3::right(), &Vec3::forward().flipped(), 50., 0.);
+        flower.branches[1].leaves = vec![leaf.clone()];
+        flower.stem_leaves = vec![leaf];
+        flower.place_instances();
+        // the stem's leaves come first.
+        assert_eq!(vec![0, 1], flower.leaf_owners);
+
+        let attach = flower.grips(Component::Leaf(1))[0].1.clone();
+        flower.move_grip(Component::Leaf(1), Grip::Tip, &(&attach + &Vec3::new(0., -80., 0.)));
+        let leaf = &flower.branches[1].leaves[0];
+        assert!((leaf.length - 80.).abs() < 1e-9);
+        assert!(leaf.rotation.rotate(&Vec3::up()).dist(&Vec3::new(0., -1., 0.)) < 1e-9);
+        // the top of the blade still faces the viewer.
+        assert!(leaf.rotation.rotate(&Vec3::forward()).z > 0.99);
+
+        // moving an arranged leaf by hand stops it being arranged.
+        flower.set_arrangement(LeafArrangement::new(Phyllotaxis::Spiral, 3));
+        flower.move_grip(Component::Leaf(0), Grip::Attach, &flower.stem_bezier(0.4));
+        assert!(flower.arrangement().is_none());
+        assert!((flower.stem_leaves[0].attach - 0.4).abs() < 0.002);
     }
 }
@@ -0,0 +1,84 @@
+// how many edits can be undone.
+const LIMIT: usize = 100;
+
+// states to step back and forth through: each edit records the state from before it.
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // an edit was made from `before`; anything undone can no longer be redone.
+    pub fn record(&mut self, before: T) {
+        if self.undo.len() == LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    // the state before the last edit, if there is one, remembering `current` to redo.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let before = self.undo.pop()?;
+        self.redo.push(current);
+        Some(before)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let after = self.redo.pop()?;
+        self.undo.push(current);
+        Some(after)
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::history::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new();
+        assert_eq!(None, history.undo(0));
+        history.record(0);
+        history.record(1);
+        assert_eq!(Some(1), history.undo(2));
+        assert_eq!(Some(0), history.undo(1));
+        assert_eq!(None, history.undo(0));
+        assert_eq!(Some(1), history.redo(0));
+
+        // a new edit drops what was undone.
+        history.record(1);
+        assert_eq!(None, history.redo(5));
+    }
+
+    #[test]
+    fn limited() {
+        let mut history = History::new();
+        for i in 0..LIMIT + 10 {
+            history.record(i);
+        }
+        let mut oldest = None;
+        while let Some(state) = history.undo(0) {
+            oldest = Some(state);
+        }
+        assert_eq!(Some(10), oldest);
+    }
+}
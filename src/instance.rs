@@ -0,0 +1,165 @@
+use crate::threed::{perpendicular, Aabb, Basis, Frame, Quat, Vec3};
+use crate::utils::exp_smin;
+
+// the rotation that turns a prototype's +y along `y`, with its +z as close to `z` as it can be.
+pub fn aim(y: &Vec3, z: &Vec3) -> Quat {
+    let y = y.unit();
+    let z = perpendicular(&y, z);
+    Quat::from_basis(&Basis::new(y.cross(&z), y, z))
+}
+
+// a shape defined once, in its own local space, to be placed any number of times.
+pub trait Prototype {
+    // `variation` is in [0, 1], so instances can differ a little without each needing their
+    // own prototype. it must not stretch the distance field by more than it shrinks it.
+    fn distance(&self, pt: &Vec3, variation: f64) -> f64;
+
+    // local bounds covering every variation.
+    fn bounds(&self) -> Aabb;
+}
+
+// one placement of a prototype: rotated, uniformly scaled, and moved to `origin`.
+#[derive(Clone)]
+pub struct Instance {
+    frame: Frame,
+    scale: f64,
+    pub variation: f64,
+}
+
+impl Instance {
+    pub fn new(origin: Vec3, rotation: &Quat, scale: f64, variation: f64) -> Self {
+        let basis = Basis::new(
+            rotation.rotate(&Vec3::right()).scale_uniform_mut(scale),
+            rotation.rotate(&Vec3::up()).scale_uniform_mut(scale),
+            rotation.rotate(&Vec3::forward()).scale_uniform_mut(scale),
+        );
+        Self {
+            frame: Frame::from_basis(origin, basis),
+            scale,
+            variation,
+        }
+    }
+
+    pub fn to_local(&self, pt: &Vec3) -> Vec3 {
+        self.frame.unproject(pt)
+    }
+
+    pub fn to_world(&self, local: &Vec3) -> Vec3 {
+        self.frame.project(local)
+    }
+
+    // scaling is uniform, so distances only need scaling back up to stay exact.
+    pub fn distance<P: Prototype>(&self, prototype: &P, pt: &Vec3) -> f64 {
+        prototype.distance(&self.to_local(pt), self.variation) * self.scale
+    }
+
+    pub fn bounds<P: Prototype>(&self, prototype: &P) -> Aabb {
+        prototype.bounds().transformed(|corner| self.to_world(corner))
+    }
+}
+
+// many instances of one prototype, blended together.
+pub struct Instanced<P: Prototype> {
+    pub prototype: P,
+    pub instances: Vec<Instance>,
+    // a sphere around the prototype, in local space, for cheap early outs.
+    center: Vec3,
+    radius: f64,
+}
+
+impl<P: Prototype> Instanced<P> {
+    pub fn new(prototype: P) -> Self {
+        let bounds = prototype.bounds();
+        let center = Vec3::lerp(&bounds.min, &bounds.max, 0.5);
+        let radius = center.dist(&bounds.max);
+        Self {
+            prototype,
+            instances: vec![],
+            center,
+            radius,
+        }
+    }
+
+    // the distance to one instance, or a cheap underestimate of it if `pt` is well outside.
+    pub fn instance_distance(&self, instance: &Instance, pt: &Vec3) -> f64 {
+        let reach = self.radius * instance.scale;
+        let to_center = instance.to_world(&self.center).dist(pt);
+        if to_center > reach * 1.1 {
+            to_center - reach
+        } else {
+            instance.distance(&self.prototype, pt)
+        }
+    }
+
+    // none if there aren't any instances.
+    pub fn distance(&self, pt: &Vec3, blend: f64) -> Option<f64> {
+        self.instances.iter()
+            .map(|instance| self.instance_distance(instance, pt))
+            .fold(None, |a, d| Some(match a {
+                None => d,
+                Some(a) => exp_smin(a, d, blend),
+            }))
+    }
+
+    // the closest instance, and how far away it is.
+    pub fn nearest(&self, pt: &Vec3) -> Option<(&Instance, f64)> {
+        self.instances.iter()
+            .map(|instance| (instance, self.instance_distance(instance, pt)))
+            .fold(None, |a: Option<(&Instance, f64)>, b| match a {
+                Some(a) if a.1 <= b.1 => Some(a),
+                _ => Some(b),
+            })
+    }
+
+    pub fn bounds(&self) -> Vec<Aabb> {
+        self.instances.iter()
+            .map(|instance| instance.bounds(&self.prototype))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instance::*;
+
+    // a sphere of radius 1 at (0, 2, 0), a little bigger with more variation.
+    struct Ball;
+
+    impl Prototype for Ball {
+        fn distance(&self, pt: &Vec3, variation: f64) -> f64 {
+            pt.dist(&Vec3::new(0., 2., 0.)) - (1. + variation)
+        }
+
+        fn bounds(&self) -> Aabb {
+            Aabb::new(Vec3::new(-2., 0., -2.), Vec3::new(2., 4., 2.))
+        }
+    }
+
+    #[test]
+    fn placed_by_frame() {
+        let turn = Quat::from_axis_angle(&Vec3::forward(), -std::f64::consts::FRAC_PI_2);
+        let instance = Instance::new(Vec3::new(10., 0., 0.), &turn, 3., 0.);
+        // the ball's center is now 6 along +x from the origin, with radius 3.
+        assert!((instance.distance(&Ball, &Vec3::new(16., 0., 0.)) + 3.).abs() < 1e-9);
+        assert!((instance.distance(&Ball, &Vec3::new(16., 5., 0.)) - 2.).abs() < 1e-9);
+
+        let bounds = instance.bounds(&Ball);
+        assert!(bounds.min.dist(&Vec3::new(10., -6., -6.)) < 1e-9, "{}", bounds.min);
+        assert!(bounds.max.dist(&Vec3::new(22., 6., 6.)) < 1e-9, "{}", bounds.max);
+    }
+
+    #[test]
+    fn nearest_instance() {
+        let mut balls = Instanced::new(Ball);
+        assert!(balls.nearest(&Vec3::zero()).is_none());
+        balls.instances.push(Instance::new(Vec3::new(0., 0., 0.), &Quat::identity(), 1., 0.));
+        balls.instances.push(Instance::new(Vec3::new(0., 10., 0.), &Quat::identity(), 1., 1.));
+
+        let (nearest, d) = balls.nearest(&Vec3::new(0., 15., 0.)).unwrap();
+        assert_eq!(1., nearest.variation);
+        assert!((d - 1.).abs() < 1e-9);
+        // far away, the early out only ever underestimates.
+        let far = Vec3::new(0., 100., 0.);
+        assert!(balls.instance_distance(&balls.instances[1], &far) <= balls.instances[1].distance(&Ball, &far));
+    }
+}
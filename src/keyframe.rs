@@ -0,0 +1,400 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::threed::Vec3;
+use crate::utils::{bezierf3, lerpf};
+
+// how a value moves from one keyframe to the next: maps the time through the segment, in
+// [0, 1], to how far along the value is. every easing starts at 0 and ends at 1, but may
+// overshoot in between.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    // like css's cubic-bezier(x1, y1, x2, y2): a curve from (0, 0) to (1, 1) with those two
+    // control points, x being time.
+    CubicBezier(f64, f64, f64, f64),
+    // overshoots and rings like a damped spring before settling. `stiffness` is how many times
+    // it swings across the segment, and `damping` how quickly the swings die down.
+    Spring { stiffness: f64, damping: f64 },
+}
+
+impl Easing {
+    pub fn ease_in_out() -> Self {
+        Easing::CubicBezier(0.42, 0., 0.58, 1.)
+    }
+
+    pub fn spring() -> Self {
+        Easing::Spring { stiffness: 3., damping: 5. }
+    }
+
+    // parses "linear", "ease", "ease-in", "ease-out", "ease-in-out", "spring",
+    // "cubic-bezier(x1, y1, x2, y2)" or "spring(stiffness, damping)".
+    pub fn parse(easing: &str) -> Result<Self, EasingParseError> {
+        let easing = easing.trim().to_lowercase();
+        match easing.as_str() {
+            "" => return Err(EasingParseError::Empty),
+            "linear" => return Ok(Easing::Linear),
+            "ease" => return Ok(Easing::CubicBezier(0.25, 0.1, 0.25, 1.)),
+            "ease-in" => return Ok(Easing::CubicBezier(0.42, 0., 1., 1.)),
+            "ease-out" => return Ok(Easing::CubicBezier(0., 0., 0.58, 1.)),
+            "ease-in-out" => return Ok(Self::ease_in_out()),
+            "spring" => return Ok(Self::spring()),
+            _ => {}
+        }
+
+        let open = easing.find('(').filter(|_| easing.ends_with(')'))
+            .ok_or_else(|| EasingParseError::Unknown(easing.clone()))?;
+        let args = easing[open + 1..easing.len() - 1]
+            .split(',')
+            .map(|arg| arg.trim().parse::<f64>()
+                .map_err(|_| EasingParseError::InvalidArgument(arg.trim().to_string())))
+            .collect::<Result<Vec<f64>, _>>()?;
+        match (easing[..open].trim(), args.as_slice()) {
+            ("cubic-bezier", &[x1, y1, x2, y2]) => {
+                // time has to keep moving forward.
+                if !(0. ..=1.).contains(&x1) || !(0. ..=1.).contains(&x2) {
+                    return Err(EasingParseError::InvalidArgument(easing.clone()));
+                }
+                Ok(Easing::CubicBezier(x1, y1, x2, y2))
+            }
+            ("spring", &[stiffness, damping]) => Ok(Easing::Spring { stiffness, damping }),
+            ("cubic-bezier", _) | ("spring", _) => Err(EasingParseError::WrongArgumentCount(easing.clone())),
+            (name, _) => Err(EasingParseError::Unknown(name.to_string())),
+        }
+    }
+
+    pub fn ease(&self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                // find where along the curve x reaches t by bisection (x only ever increases,
+                // since x1 and x2 are in [0, 1]), then read off y there.
+                let (mut lo, mut hi) = (0., 1.);
+                for _ in 0..32 {
+                    let mid = (lo + hi) / 2.;
+                    if bezierf3(0., *x1, *x2, 1., mid) < t {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                bezierf3(0., *y1, *y2, 1., (lo + hi) / 2.)
+            }
+            Easing::Spring { stiffness, damping } => {
+                // a damped oscillation about 1, pinned to land exactly on 1 at the end.
+                let swing = (-damping * t).exp() * (PI * 2. * stiffness * t).cos();
+                1. - swing * (1. - t)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EasingParseError {
+    Empty,
+    Unknown(String),
+    InvalidArgument(String),
+    WrongArgumentCount(String),
+}
+
+impl fmt::Display for EasingParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            EasingParseError::Empty => write!(f, "easing is empty"),
+            EasingParseError::Unknown(s) => write!(f, "unknown easing '{}'", s),
+            EasingParseError::InvalidArgument(s) => write!(f, "invalid easing argument '{}'", s),
+            EasingParseError::WrongArgumentCount(s) =>
+                write!(f, "'{}' should be cubic-bezier(x1, y1, x2, y2) or spring(stiffness, damping)", s),
+        }
+    }
+}
+
+impl Error for EasingParseError {}
+
+// values that can be blended between keyframes.
+pub trait Interpolate: Clone {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        lerpf(*a, *b, t)
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(a: &Self, b: &Self, t: f64) -> Self {
+        Vec3::lerp(a, b, t)
+    }
+}
+
+// a value at a moment in time, and how it eases toward the next keyframe.
+#[derive(Clone, Debug)]
+pub struct Keyframe<T> {
+    // in milliseconds.
+    pub time: f64,
+    pub value: T,
+    pub easing: Easing,
+}
+
+// keyframes for one value, kept in time order.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Self {
+        Self { keyframes: vec![] }
+    }
+
+    // adds a keyframe, replacing any already at exactly that time.
+    pub fn insert(&mut self, keyframe: Keyframe<T>) {
+        match self.keyframes.iter().position(|k| k.time >= keyframe.time) {
+            Some(i) if self.keyframes[i].time == keyframe.time => self.keyframes[i] = keyframe,
+            Some(i) => self.keyframes.insert(i, keyframe),
+            None => self.keyframes.push(keyframe),
+        }
+    }
+
+    // when the last keyframe is.
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0., |k| k.time)
+    }
+
+    // the value at `time`: held at the first keyframe before it, and at the last after. none if
+    // there aren't any keyframes.
+    pub fn sample(&self, time: f64) -> Option<T> {
+        let next = self.keyframes.iter().position(|k| k.time > time);
+        match next {
+            None => self.keyframes.last().map(|k| k.value.clone()),
+            Some(0) => Some(self.keyframes[0].value.clone()),
+            Some(i) => {
+                let (a, b) = (&self.keyframes[i - 1], &self.keyframes[i]);
+                let t = (time - a.time) / (b.time - a.time);
+                Some(T::interpolate(&a.value, &b.value, a.easing.ease(t)))
+            }
+        }
+    }
+}
+
+impl<T: Interpolate> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// plant parameters that can be keyframed alongside the handles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Param {
+    // how far grown the plant is, from seedling (0) to as designed (1).
+    Growth,
+    // how far the plant is turned about its stem, in radians.
+    Turn,
+}
+
+impl Param {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "growth" => Some(Param::Growth),
+            "turn" => Some(Param::Turn),
+            _ => None,
+        }
+    }
+}
+
+// the handle positions and parameter values at one moment of an animation. handles without
+// keyframes are none, and left where they are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub handles: Vec<Option<Vec3>>,
+    pub params: Vec<(Param, f64)>,
+}
+
+// keyframed handle positions and plant parameters, played back in wall-clock milliseconds.
+pub struct Animation {
+    // one track per handle, by index.
+    handles: Vec<Track<Vec3>>,
+    params: Vec<(Param, Track<f64>)>,
+    looping: bool,
+    // when it started playing, while it's playing.
+    started: Option<f64>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self {
+            handles: vec![],
+            params: vec![],
+            looping: false,
+            started: None,
+        }
+    }
+
+    pub fn add_handle_keyframe(&mut self, handle: usize, keyframe: Keyframe<Vec3>) {
+        if self.handles.len() <= handle {
+            self.handles.resize_with(handle + 1, Track::new);
+        }
+        self.handles[handle].insert(keyframe);
+    }
+
+    pub fn add_param_keyframe(&mut self, param: Param, keyframe: Keyframe<f64>) {
+        match self.params.iter_mut().find(|(p, _)| *p == param) {
+            Some((_, track)) => track.insert(keyframe),
+            None => {
+                let mut track = Track::new();
+                track.insert(keyframe);
+                self.params.push((param, track));
+            }
+        }
+    }
+
+    // removes every keyframe, and stops.
+    pub fn clear(&mut self) {
+        self.handles.clear();
+        self.params.clear();
+        self.started = None;
+    }
+
+    // when the last keyframe of any track is.
+    pub fn duration(&self) -> f64 {
+        self.handles.iter()
+            .map(|track| track.duration())
+            .chain(self.params.iter().map(|(_, track)| track.duration()))
+            .fold(0., f64::max)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.started.is_some()
+    }
+
+    // plays from the start. a looping animation wraps back around after its last keyframe;
+    // otherwise it stops there.
+    pub fn play(&mut self, now: f64, looping: bool) {
+        self.looping = looping;
+        self.started = Some(now);
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    pub fn sample(&self, time: f64) -> Pose {
+        Pose {
+            handles: self.handles.iter().map(|track| track.sample(time)).collect(),
+            params: self.params.iter()
+                .filter_map(|(param, track)| track.sample(time).map(|value| (*param, value)))
+                .collect(),
+        }
+    }
+
+    // the pose at `now`, if it's playing.
+    pub fn tick(&mut self, now: f64) -> Option<Pose> {
+        let elapsed = now - self.started?;
+        let duration = self.duration();
+        let time = if self.looping && duration > 0. {
+            elapsed.rem_euclid(duration)
+        } else {
+            if elapsed >= duration {
+                self.started = None;
+            }
+            elapsed
+        };
+        Some(self.sample(time))
+    }
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keyframe::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn easings_start_and_end_in_place() {
+        for easing in &[Easing::Linear, Easing::ease_in_out(), Easing::spring(), Easing::CubicBezier(0.3, -0.5, 0.7, 1.6)] {
+            assert!(close(0., easing.ease(0.)), "{:?}", easing);
+            assert!(close(1., easing.ease(1.)), "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn cubic_bezier() {
+        let ease = Easing::ease_in_out();
+        assert!(close(0.5, ease.ease(0.5)));
+        assert!(ease.ease(0.2) < 0.2 && ease.ease(0.8) > 0.8);
+        // with control points on the diagonal, it's linear.
+        let linear = Easing::CubicBezier(1. / 3., 1. / 3., 2. / 3., 2. / 3.);
+        assert!(close(0.3, linear.ease(0.3)));
+    }
+
+    #[test]
+    fn spring_overshoots() {
+        let spring = Easing::spring();
+        assert!((0..100).map(|i| spring.ease(i as f64 / 100.)).any(|x| x > 1.));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Ok(Easing::Linear), Easing::parse(" Linear "));
+        assert_eq!(Ok(Easing::CubicBezier(0.1, 0.2, 0.3, 0.4)), Easing::parse("cubic-bezier(0.1, 0.2, 0.3, 0.4)"));
+        assert_eq!(Ok(Easing::Spring { stiffness: 2., damping: 1. }), Easing::parse("spring(2, 1)"));
+        assert_eq!(Err(EasingParseError::Empty), Easing::parse(""));
+        assert_eq!(Err(EasingParseError::Unknown("bounce".to_string())), Easing::parse("bounce"));
+        assert_eq!(Err(EasingParseError::InvalidArgument("x".to_string())), Easing::parse("spring(x, 1)"));
+        assert_eq!(
+            Err(EasingParseError::WrongArgumentCount("cubic-bezier(1, 2)".to_string())),
+            Easing::parse("cubic-bezier(1, 2)"));
+        assert!(Easing::parse("cubic-bezier(2, 0, 0, 1)").is_err());
+    }
+
+    #[test]
+    fn track() {
+        let mut track = Track::new();
+        assert_eq!(None, track.sample(0.));
+        track.insert(Keyframe { time: 1000., value: 10., easing: Easing::Linear });
+        track.insert(Keyframe { time: 0., value: 0., easing: Easing::Linear });
+        track.insert(Keyframe { time: 2000., value: 0., easing: Easing::Linear });
+        assert_eq!(2000., track.duration());
+        assert_eq!(Some(0.), track.sample(-5.));
+        assert_eq!(Some(5.), track.sample(500.));
+        assert_eq!(Some(5.), track.sample(1500.));
+        assert_eq!(Some(0.), track.sample(3000.));
+
+        track.insert(Keyframe { time: 1000., value: 20., easing: Easing::Linear });
+        assert_eq!(Some(10.), track.sample(500.));
+    }
+
+    #[test]
+    fn animation_loops() {
+        let mut animation = Animation::new();
+        let key = |time, x| Keyframe { time, value: Vec3::new(x, 0., 0.), easing: Easing::Linear };
+        animation.add_handle_keyframe(1, key(0., 0.));
+        animation.add_handle_keyframe(1, key(1000., 10.));
+        animation.add_handle_keyframe(1, key(2000., 0.));
+        animation.add_param_keyframe(Param::Growth, Keyframe { time: 0., value: 0., easing: Easing::Linear });
+        animation.add_param_keyframe(Param::Growth, Keyframe { time: 500., value: 1., easing: Easing::Linear });
+        assert_eq!(2000., animation.duration());
+        assert_eq!(None, animation.tick(0.));
+
+        animation.play(100., true);
+        let pose = animation.tick(2600.).unwrap();
+        assert_eq!(vec![None, Some(Vec3::new(5., 0., 0.))], pose.handles);
+        assert_eq!(vec![(Param::Growth, 1.)], pose.params);
+        assert!(animation.is_playing());
+
+        animation.play(0., false);
+        assert_eq!(Some(Vec3::zero()), animation.tick(2500.).unwrap().handles[1]);
+        assert!(!animation.is_playing());
+    }
+}
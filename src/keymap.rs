@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::fmt;
+
+// something the editor can do from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    ToggleRenderMode,
+    ResetView,
+    NextHandle,
+    PreviousHandle,
+    // moves the selected handles by this many screen pixels.
+    Nudge(i32, i32),
+    Undo,
+    Redo,
+}
+
+// how far a far nudge goes, in screen pixels.
+const FAR: i32 = 10;
+
+const COMMANDS: [(&str, Command); 14] = [
+    ("toggle-render-mode", Command::ToggleRenderMode),
+    ("reset-view", Command::ResetView),
+    ("next-handle", Command::NextHandle),
+    ("previous-handle", Command::PreviousHandle),
+    ("nudge-left", Command::Nudge(-1, 0)),
+    ("nudge-right", Command::Nudge(1, 0)),
+    ("nudge-up", Command::Nudge(0, -1)),
+    ("nudge-down", Command::Nudge(0, 1)),
+    ("nudge-left-far", Command::Nudge(-FAR, 0)),
+    ("nudge-right-far", Command::Nudge(FAR, 0)),
+    ("nudge-up-far", Command::Nudge(0, -FAR)),
+    ("nudge-down-far", Command::Nudge(0, FAR)),
+    ("undo", Command::Undo),
+    ("redo", Command::Redo),
+];
+
+impl Command {
+    pub fn from_name(name: &str) -> Option<Command> {
+        COMMANDS.iter().find(|(n, _)| *n == name).map(|(_, command)| *command)
+    }
+}
+
+// a key pressed with some modifiers, like "Ctrl+Shift+Z". keys are named as in a keyboard
+// event's `key`, with letters in either case.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chord {
+    key: String,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+}
+
+impl Chord {
+    pub fn new(key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> Self {
+        // shift changes which letter a key event reports, but it's already its own modifier.
+        let key = if key.chars().count() == 1 { key.to_lowercase() } else { key.to_string() };
+        Self { key, ctrl, alt, shift, meta }
+    }
+
+    pub fn parse(chord: &str) -> Result<Self, KeymapError> {
+        let chord = chord.trim();
+        // the last part is the key, which may itself be "+".
+        let (modifiers, key) = if chord == "+" || chord.ends_with("++") {
+            (&chord[..chord.len() - 1], "+")
+        } else {
+            match chord.rfind('+') {
+                Some(i) => (&chord[..i], &chord[i + 1..]),
+                None => ("", chord),
+            }
+        };
+        if key.trim().is_empty() {
+            return Err(KeymapError::MissingKey(chord.to_string()));
+        }
+        let (mut ctrl, mut alt, mut shift, mut meta) = (false, false, false, false);
+        for modifier in modifiers.split('+').map(str::trim).filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => ctrl = true,
+                "alt" | "option" => alt = true,
+                "shift" => shift = true,
+                "meta" | "cmd" | "command" => meta = true,
+                _ => return Err(KeymapError::UnknownModifier(modifier.to_string())),
+            }
+        }
+        Ok(Self::new(key.trim(), ctrl, alt, shift, meta))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeymapError {
+    MissingKey(String),
+    UnknownModifier(String),
+    UnknownCommand(String),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            KeymapError::MissingKey(s) => write!(f, "'{}' doesn't name a key", s),
+            KeymapError::UnknownModifier(s) => write!(f, "unknown modifier '{}'", s),
+            KeymapError::UnknownCommand(s) => write!(f, "unknown command '{}'", s),
+        }
+    }
+}
+
+impl Error for KeymapError {}
+
+// which chord runs which command. each chord runs at most one, but a command can have several.
+pub struct Keymap {
+    bindings: Vec<(Chord, Command)>,
+}
+
+impl Keymap {
+    pub fn empty() -> Self {
+        Self { bindings: vec![] }
+    }
+
+    // binds a chord, given as for `Chord::parse`, to a command, named as for
+    // `Command::from_name`, replacing whatever it ran before.
+    pub fn bind(&mut self, chord: &str, command: &str) -> Result<(), KeymapError> {
+        let command = Command::from_name(command)
+            .ok_or_else(|| KeymapError::UnknownCommand(command.to_string()))?;
+        let chord = Chord::parse(chord)?;
+        self.bindings.retain(|(c, _)| *c != chord);
+        self.bindings.push((chord, command));
+        Ok(())
+    }
+
+    // leaves the chord to the browser again.
+    pub fn unbind(&mut self, chord: &str) -> Result<(), KeymapError> {
+        let chord = Chord::parse(chord)?;
+        self.bindings.retain(|(c, _)| *c != chord);
+        Ok(())
+    }
+
+    pub fn command(&self, chord: &Chord) -> Option<Command> {
+        self.bindings.iter().find(|(c, _)| c == chord).map(|(_, command)| *command)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        for (chord, command) in &[
+            ("R", "toggle-render-mode"),
+            ("0", "reset-view"),
+            ("Tab", "next-handle"),
+            ("Shift+Tab", "previous-handle"),
+            ("ArrowLeft", "nudge-left"),
+            ("ArrowRight", "nudge-right"),
+            ("ArrowUp", "nudge-up"),
+            ("ArrowDown", "nudge-down"),
+            ("Shift+ArrowLeft", "nudge-left-far"),
+            ("Shift+ArrowRight", "nudge-right-far"),
+            ("Shift+ArrowUp", "nudge-up-far"),
+            ("Shift+ArrowDown", "nudge-down-far"),
+            ("Ctrl+Z", "undo"),
+            ("Meta+Z", "undo"),
+            ("Ctrl+Shift+Z", "redo"),
+            ("Meta+Shift+Z", "redo"),
+            ("Ctrl+Y", "redo"),
+        ] {
+            keymap.bind(chord, command).unwrap();
+        }
+        keymap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keymap::*;
+
+    #[test]
+    fn parse_chords() {
+        assert_eq!(Ok(Chord::new("z", true, false, true, false)), Chord::parse("Ctrl+Shift+Z"));
+        assert_eq!(Ok(Chord::new("ArrowLeft", false, false, false, false)), Chord::parse(" ArrowLeft "));
+        assert_eq!(Ok(Chord::new("+", true, false, false, false)), Chord::parse("Ctrl++"));
+        assert_eq!(Ok(Chord::new("+", false, false, false, false)), Chord::parse("+"));
+        assert_eq!(Err(KeymapError::MissingKey("Ctrl+".to_string())), Chord::parse("Ctrl+"));
+        assert_eq!(Err(KeymapError::UnknownModifier("Hyper".to_string())), Chord::parse("Hyper+A"));
+    }
+
+    #[test]
+    fn defaults() {
+        let keymap = Keymap::default();
+        // a key event for shift+z reports "Z".
+        assert_eq!(Some(Command::Redo), keymap.command(&Chord::new("Z", true, false, true, false)));
+        assert_eq!(Some(Command::Undo), keymap.command(&Chord::new("z", true, false, false, false)));
+        assert_eq!(Some(Command::Nudge(0, -10)), keymap.command(&Chord::new("ArrowUp", false, false, true, false)));
+        assert_eq!(None, keymap.command(&Chord::new("z", false, false, false, false)));
+    }
+
+    #[test]
+    fn override_bindings() {
+        let mut keymap = Keymap::default();
+        keymap.bind("R", "reset-view").unwrap();
+        keymap.unbind("Tab").unwrap();
+        assert_eq!(Some(Command::ResetView), keymap.command(&Chord::parse("r").unwrap()));
+        assert_eq!(None, keymap.command(&Chord::parse("Tab").unwrap()));
+        assert_eq!(Err(KeymapError::UnknownCommand("fly".to_string())), keymap.bind("F", "fly"));
+    }
+}
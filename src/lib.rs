@@ -7,6 +7,27 @@ mod canvas;
 mod color;
 mod sdf;
 mod flower;
+mod viewport;
+mod tiles;
+mod render;
+mod sdf_cache;
+mod palette;
+mod watercolor;
+mod rng;
+mod noise;
+mod lsystem;
+mod phyllotaxis;
+mod instance;
+mod thorn;
+mod sepal;
+mod timeline;
+mod export;
+mod keyframe;
+mod physics;
+mod pointer;
+mod keymap;
+mod history;
+mod snap;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -27,3 +48,16 @@ pub fn setup() -> canvas::Canvas {
     let mut canvas = canvas::Canvas::new(canvas);
     canvas
 }
+
+// normalizes any css color string to hex, or throws a descriptive error for bad input.
+#[wasm_bindgen]
+pub fn parse_color(color: &str) -> Result<String, JsValue> {
+    color::Color::parse(color)
+        .map(|c| c.as_hexstring())
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn palette_names() -> Vec<JsValue> {
+    palette::PALETTE_NAMES.iter().map(|name| JsValue::from_str(name)).collect()
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::threed::Vec3;
+
+// expanded strings beyond this many symbols are almost certainly a runaway grammar.
+const MAX_SYMBOLS: usize = 100_000;
+
+// and any growing grammar passes MAX_SYMBOLS long before this many iterations; a grammar that
+// doesn't grow could otherwise be rewritten forever.
+const MAX_ITERATIONS: usize = 64;
+
+// a bracketed l-system describing plant architecture. rules rewrite symbols in parallel, and
+// the expanded string is read by a turtle that starts at the origin heading up the screen:
+//  F     grow forward by `step`
+//  + -   turn right/left by `angle` (radians)
+//  [ ]   start/end a side branch, attached where the turtle is now
+//  L     a leaf, pointing the way the turtle is heading
+//  K     a flower at the tip of the current branch
+// any other symbol is only there to be rewritten.
+#[derive(Clone, Debug)]
+pub struct LSystem {
+    pub axiom: String,
+    pub rules: HashMap<char, String>,
+    pub angle: f64,
+    pub step: f64,
+}
+
+// one axis of the plant as the turtle drew it: the stem itself, or a side branch of it.
+#[derive(Clone, Debug, Default)]
+pub struct Shoot {
+    // every point the turtle grew through, starting from (0, 0, 0) where the shoot attaches.
+    pub path: Vec<Vec3>,
+    // side shoots, and how far along this one (by length, in [0, 1]) they attach.
+    pub branches: Vec<(f64, Shoot)>,
+    // leaves, as where they attach and the direction they point.
+    pub leaves: Vec<(f64, Vec3)>,
+    pub flower: bool,
+}
+
+impl Shoot {
+    pub fn length(&self) -> f64 {
+        self.path.windows(2).map(|p| p[0].dist(&p[1])).sum()
+    }
+
+    // the point a fraction `t` of the way along the shoot, by length.
+    pub fn point_at(&self, t: f64) -> Vec3 {
+        let target = self.length() * t;
+        let mut walked = 0.;
+        for p in self.path.windows(2) {
+            let d = p[0].dist(&p[1]);
+            if walked + d >= target && d > 0. {
+                return Vec3::lerp(&p[0], &p[1], (target - walked) / d);
+            }
+            walked += d;
+        }
+        self.tip()
+    }
+
+    pub fn tip(&self) -> Vec3 {
+        self.path.last().cloned().unwrap_or_else(Vec3::zero)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LSystemError {
+    UnbalancedBrackets,
+    NoStem,
+    TooLarge(usize),
+    TooManyIterations(usize),
+    InvalidStatement(String),
+    InvalidNumber(String),
+}
+
+impl fmt::Display for LSystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            LSystemError::UnbalancedBrackets => write!(f, "branch brackets don't balance"),
+            LSystemError::NoStem => write!(f, "the main stem never grows; it needs an 'F' outside brackets"),
+            LSystemError::TooLarge(n) =>
+                write!(f, "expansion grew past {} symbols; try fewer iterations", n),
+            LSystemError::TooManyIterations(n) => write!(f, "at most {} iterations are allowed", n),
+            LSystemError::InvalidStatement(s) =>
+                write!(f, "'{}' is not 'axiom=...', 'angle=...', 'step=...' or a rule like 'A=F[+A]'", s),
+            LSystemError::InvalidNumber(s) => write!(f, "invalid number '{}'", s),
+        }
+    }
+}
+
+impl Error for LSystemError {}
+
+impl LSystem {
+    pub fn new(axiom: &str, angle: f64, step: f64) -> Self {
+        Self {
+            axiom: axiom.to_string(),
+            rules: HashMap::new(),
+            angle,
+            step,
+        }
+    }
+
+    pub fn rule(mut self, symbol: char, replacement: &str) -> Self {
+        self.rules.insert(symbol, replacement.to_string());
+        self
+    }
+
+    // a small rose bush: a cane that keeps growing canes, which fork a little, each topped with a
+    // flower. about four iterations fill a typical canvas.
+    pub fn rose_bush() -> Self {
+        LSystem::new("AK", 28f64.to_radians(), 18.)
+            .rule('A', "FF[+L]F[-BK]FF[-L]F[+BK]A")
+            .rule('B', "F[-L]F[+CK]F[+L]B")
+            .rule('C', "F[-L]FC")
+    }
+
+    // reads statements separated by ';' or newlines, e.g.
+    //   axiom=A; angle=25; step=20; A=F[+A][-L]FK
+    // angles are in degrees.
+    pub fn parse(source: &str) -> Result<Self, LSystemError> {
+        let mut lsystem = LSystem::new("", 25f64.to_radians(), 20.);
+        let number = |s: &str| s.parse::<f64>()
+            .map_err(|_| LSystemError::InvalidNumber(s.to_string()));
+
+        for statement in source.split(&[';', '\n'][..]) {
+            let statement = statement.trim();
+            if statement.is_empty() {
+                continue;
+            }
+            let invalid = || LSystemError::InvalidStatement(statement.to_string());
+            let eq = statement.find('=').ok_or_else(invalid)?;
+            let (key, value) = (statement[..eq].trim(), statement[eq + 1..].trim());
+            match key {
+                "axiom" => lsystem.axiom = value.to_string(),
+                "angle" => lsystem.angle = number(value)?.to_radians(),
+                "step" => lsystem.step = number(value)?,
+                _ => {
+                    let mut chars = key.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(symbol), None) => lsystem.rules.insert(symbol, value.to_string()),
+                        _ => return Err(invalid()),
+                    };
+                }
+            }
+        }
+
+        if lsystem.axiom.is_empty() {
+            return Err(LSystemError::InvalidStatement("axiom=".to_string()));
+        }
+        Ok(lsystem)
+    }
+
+    pub fn expand(&self, iterations: usize) -> Result<String, LSystemError> {
+        if iterations > MAX_ITERATIONS {
+            return Err(LSystemError::TooManyIterations(MAX_ITERATIONS));
+        }
+        let mut symbols = self.axiom.clone();
+        for _ in 0..iterations {
+            let mut next = String::with_capacity(symbols.len() * 2);
+            for c in symbols.chars() {
+                match self.rules.get(&c) {
+                    Some(replacement) => next.push_str(replacement),
+                    None => next.push(c),
+                }
+                if next.len() > MAX_SYMBOLS {
+                    return Err(LSystemError::TooLarge(MAX_SYMBOLS));
+                }
+            }
+            // nothing changed, so nothing ever will.
+            if next == symbols {
+                break;
+            }
+            symbols = next;
+        }
+        Ok(symbols)
+    }
+
+    // expands the grammar and walks the turtle over it, giving the main shoot.
+    pub fn grow(&self, iterations: usize) -> Result<Shoot, LSystemError> {
+        self.interpret(&self.expand(iterations)?)
+    }
+
+    pub fn interpret(&self, symbols: &str) -> Result<Shoot, LSystemError> {
+        let mut stack = vec![Turtle::new(Vec3::new(0., -1., 0.))];
+
+        for c in symbols.chars() {
+            let turtle = stack.last_mut().unwrap();
+            match c {
+                'F' => {
+                    let next = turtle.position().sadd_vec_mut(self.step, &turtle.heading);
+                    turtle.length += self.step;
+                    turtle.shoot.path.push(next);
+                }
+                '+' => turtle.turn(self.angle),
+                '-' => turtle.turn(-self.angle),
+                'L' => {
+                    let leaf = (turtle.length, turtle.heading.clone());
+                    turtle.shoot.leaves.push(leaf);
+                }
+                'K' => turtle.shoot.flower = true,
+                '[' => {
+                    let heading = turtle.heading.clone();
+                    stack.push(Turtle::new(heading));
+                }
+                ']' => {
+                    if stack.len() < 2 {
+                        return Err(LSystemError::UnbalancedBrackets);
+                    }
+                    let branch = stack.pop().unwrap().finish();
+                    let parent = stack.last_mut().unwrap();
+                    parent.shoot.branches.push((parent.length, branch));
+                }
+                _ => {}
+            }
+        }
+
+        if stack.len() != 1 {
+            return Err(LSystemError::UnbalancedBrackets);
+        }
+        Ok(stack.pop().unwrap().finish())
+    }
+}
+
+struct Turtle {
+    heading: Vec3,
+    // how far this shoot has grown so far. attach points are recorded as lengths, and only
+    // become fractions of the shoot once it's finished.
+    length: f64,
+    shoot: Shoot,
+}
+
+impl Turtle {
+    fn new(heading: Vec3) -> Self {
+        Self {
+            heading,
+            length: 0.,
+            shoot: Shoot {
+                path: vec![Vec3::zero()],
+                ..Default::default()
+            },
+        }
+    }
+
+    fn position(&self) -> Vec3 {
+        self.shoot.tip()
+    }
+
+    // turns in the picture plane; positive angles turn clockwise on screen, since y points down.
+    fn turn(&mut self, angle: f64) {
+        let (sin, cos) = angle.sin_cos();
+        let h = &self.heading;
+        self.heading = Vec3::new(h.x * cos - h.y * sin, h.x * sin + h.y * cos, h.z);
+    }
+
+    fn finish(mut self) -> Shoot {
+        let length = self.length;
+        let fraction = |l: f64| if length > 0. { l / length } else { 0. };
+        for (attach, _) in self.shoot.branches.iter_mut() {
+            *attach = fraction(*attach);
+        }
+        for (attach, _) in self.shoot.leaves.iter_mut() {
+            *attach = fraction(*attach);
+        }
+        self.shoot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lsystem::*;
+
+    #[test]
+    fn expand() {
+        let algae = LSystem::new("A", 0., 1.).rule('A', "AB").rule('B', "A");
+        assert_eq!("A", algae.expand(0).unwrap());
+        assert_eq!("ABAABABA", algae.expand(4).unwrap());
+        assert_eq!(Err(LSystemError::TooLarge(MAX_SYMBOLS)), algae.expand(40));
+    }
+
+    #[test]
+    fn expand_without_growing() {
+        let still = LSystem::new("FA", 0., 1.).rule('A', "A");
+        assert_eq!("FA", still.expand(MAX_ITERATIONS).unwrap());
+        assert_eq!(Err(LSystemError::TooManyIterations(MAX_ITERATIONS)), still.expand(4_000_000_000));
+    }
+
+    #[test]
+    fn interpret_branches() {
+        let lsystem = LSystem::new("", std::f64::consts::FRAC_PI_2, 10.);
+        let shoot = lsystem.interpret("F[+FK]FL").unwrap();
+
+        assert_eq!(20., shoot.length());
+        assert_eq!(Vec3::new(0., -20., 0.), shoot.tip());
+        assert_eq!(Vec3::new(0., -5., 0.), shoot.point_at(0.25));
+        assert_eq!(1, shoot.branches.len());
+        assert_eq!(0.5, shoot.branches[0].0);
+        let branch = &shoot.branches[0].1;
+        assert!(branch.flower);
+        assert!(branch.tip().dist(&Vec3::new(10., 0., 0.)) < 1e-9);
+        assert_eq!(1, shoot.leaves.len());
+        assert_eq!(1., shoot.leaves[0].0);
+        assert!(shoot.branches[0].1.leaves.is_empty());
+    }
+
+    #[test]
+    fn unbalanced() {
+        let lsystem = LSystem::new("", 0.1, 1.);
+        assert_eq!(Err(LSystemError::UnbalancedBrackets), lsystem.interpret("F[F").map(|_| ()));
+        assert_eq!(Err(LSystemError::UnbalancedBrackets), lsystem.interpret("F]F").map(|_| ()));
+    }
+
+    #[test]
+    fn parse() {
+        let lsystem = LSystem::parse("axiom=A; angle=90\nstep=5; A = F[+A]").unwrap();
+        assert_eq!("A", lsystem.axiom);
+        assert_eq!(5., lsystem.step);
+        assert!((lsystem.angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert_eq!("F[+A]", lsystem.rules[&'A']);
+
+        assert!(LSystem::parse("A=F").is_err());
+        assert_eq!(
+            Err(LSystemError::InvalidNumber("wide".to_string())),
+            LSystem::parse("axiom=A; angle=wide").map(|_| ()));
+        assert_eq!(
+            Err(LSystemError::InvalidStatement("AB=F".to_string())),
+            LSystem::parse("axiom=A; AB=F").map(|_| ()));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::rng::Rng;
+use crate::threed::Vec3;
+use crate::utils::lerpf;
+
+// seeded coherent noise. everything here is a pure function of the seed and the input point,
+// so it can be used both to displace distance fields and to texture surfaces.
+//
+// perlin, simplex and value noise return values in roughly [-1, 1]; worley returns a distance.
+#[derive(Clone)]
+pub struct Noise {
+    perm: Vec<usize>,
+}
+
+// fractal sum of octaves of some base noise.
+#[derive(Clone, Debug)]
+pub struct Fbm {
+    pub octaves: usize,
+    // frequency multiplier per octave.
+    pub lacunarity: f64,
+    // amplitude multiplier per octave.
+    pub gain: f64,
+}
+
+impl Default for Fbm {
+    fn default() -> Self {
+        Self { octaves: 4, lacunarity: 2., gain: 0.5 }
+    }
+}
+
+impl Fbm {
+    pub fn sample2<F: Fn(f64, f64) -> f64>(&self, noise: F, x: f64, y: f64) -> f64 {
+        let mut sum = 0.;
+        let mut norm = 0.;
+        let mut amplitude = 1.;
+        let mut frequency = 1.;
+        for _ in 0..self.octaves {
+            sum += amplitude * noise(x * frequency, y * frequency);
+            norm += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        sum / norm
+    }
+
+    pub fn sample3<F: Fn(&Vec3) -> f64>(&self, noise: F, p: &Vec3) -> f64 {
+        let mut sum = 0.;
+        let mut norm = 0.;
+        let mut amplitude = 1.;
+        let mut frequency = 1.;
+        for _ in 0..self.octaves {
+            sum += amplitude * noise(&(p * frequency));
+            norm += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        sum / norm
+    }
+}
+
+// perlin3 never changes faster than this per unit step (sampling finds slopes up to about 3).
+// fields displaced by it need rescaling by this much to stay conservative for raymarching.
+pub const PERLIN3_SLOPE: f64 = 3.5;
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut perm: Vec<usize> = (0..256).collect();
+        Rng::new(seed).shuffle(&mut perm);
+        // doubled, so lookups like perm[perm[x] + y] never need wrapping.
+        let doubled = perm.iter().chain(perm.iter()).cloned().collect();
+        Self { perm: doubled }
+    }
+
+    fn hash2(&self, x: i64, y: i64) -> usize {
+        self.perm[self.perm[(x & 255) as usize] + (y & 255) as usize]
+    }
+
+    fn hash3(&self, x: i64, y: i64, z: i64) -> usize {
+        self.perm[self.hash2(x, y) + (z & 255) as usize]
+    }
+
+    // https://mrl.cs.nyu.edu/~perlin/noise/
+    pub fn perlin2(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i64, y0 as i64);
+        let (u, v) = (fade(fx), fade(fy));
+
+        let n00 = grad2(self.hash2(ix, iy), fx, fy);
+        let n10 = grad2(self.hash2(ix + 1, iy), fx - 1., fy);
+        let n01 = grad2(self.hash2(ix, iy + 1), fx, fy - 1.);
+        let n11 = grad2(self.hash2(ix + 1, iy + 1), fx - 1., fy - 1.);
+        lerpf(lerpf(n00, n10, u), lerpf(n01, n11, u), v)
+    }
+
+    pub fn perlin3(&self, p: &Vec3) -> f64 {
+        let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (fx, fy, fz) = (p.x - x0, p.y - y0, p.z - z0);
+        let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+
+        let corner = |dx: i64, dy: i64, dz: i64| grad3(
+            self.hash3(ix + dx, iy + dy, iz + dz),
+            fx - dx as f64,
+            fy - dy as f64,
+            fz - dz as f64,
+        );
+        lerpf(
+            lerpf(
+                lerpf(corner(0, 0, 0), corner(1, 0, 0), u),
+                lerpf(corner(0, 1, 0), corner(1, 1, 0), u),
+                v,
+            ),
+            lerpf(
+                lerpf(corner(0, 0, 1), corner(1, 0, 1), u),
+                lerpf(corner(0, 1, 1), corner(1, 1, 1), u),
+                v,
+            ),
+            w,
+        )
+    }
+
+    // http://staffwww.itn.liu.se/~stegu/simplexnoise/simplexnoise.pdf
+    pub fn simplex2(&self, x: f64, y: f64) -> f64 {
+        let f2 = 0.5 * (3f64.sqrt() - 1.);
+        let g2 = (3. - 3f64.sqrt()) / 6.;
+
+        let s = (x + y) * f2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * g2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let corners = [
+            (x0, y0, 0, 0),
+            (x0 - i1 as f64 + g2, y0 - j1 as f64 + g2, i1, j1),
+            (x0 - 1. + 2. * g2, y0 - 1. + 2. * g2, 1, 1),
+        ];
+        let (i, j) = (i as i64, j as i64);
+        let sum: f64 = corners.iter()
+            .map(|&(cx, cy, di, dj)| {
+                let t = 0.5 - cx * cx - cy * cy;
+                if t < 0. {
+                    return 0.;
+                }
+                let t = t * t;
+                t * t * grad2(self.hash2(i + di, j + dj), cx, cy)
+            })
+            .sum();
+        70. * sum
+    }
+
+    pub fn simplex3(&self, p: &Vec3) -> f64 {
+        let f3 = 1. / 3.;
+        let g3 = 1. / 6.;
+
+        let s = (p.x + p.y + p.z) * f3;
+        let (i, j, k) = ((p.x + s).floor(), (p.y + s).floor(), (p.z + s).floor());
+        let t = (i + j + k) * g3;
+        let (x0, y0, z0) = (p.x - (i - t), p.y - (j - t), p.z - (k - t));
+
+        // which of the six tetrahedra of the skewed cube we're in.
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let corners = [
+            (0, 0, 0, 0.),
+            (i1, j1, k1, g3),
+            (i2, j2, k2, 2. * g3),
+            (1, 1, 1, 3. * g3),
+        ];
+        let (i, j, k) = (i as i64, j as i64, k as i64);
+        let sum: f64 = corners.iter()
+            .map(|&(di, dj, dk, offset)| {
+                let cx = x0 - di as f64 + offset;
+                let cy = y0 - dj as f64 + offset;
+                let cz = z0 - dk as f64 + offset;
+                let t = 0.6 - cx * cx - cy * cy - cz * cz;
+                if t < 0. {
+                    return 0.;
+                }
+                let t = t * t;
+                t * t * grad3(self.hash3(i + di, j + dj, k + dk), cx, cy, cz)
+            })
+            .sum();
+        32. * sum
+    }
+
+    // smoothly interpolated random values on the integer lattice.
+    pub fn value2(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (u, v) = (fade(x - x0), fade(y - y0));
+        let (ix, iy) = (x0 as i64, y0 as i64);
+        let at = |dx: i64, dy: i64| self.hash2(ix + dx, iy + dy) as f64 / 127.5 - 1.;
+        lerpf(lerpf(at(0, 0), at(1, 0), u), lerpf(at(0, 1), at(1, 1), u), v)
+    }
+
+    pub fn value3(&self, p: &Vec3) -> f64 {
+        let (x0, y0, z0) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (fade(p.x - x0), fade(p.y - y0), fade(p.z - z0));
+        let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+        let at = |dx: i64, dy: i64, dz: i64| {
+            self.hash3(ix + dx, iy + dy, iz + dz) as f64 / 127.5 - 1.
+        };
+        lerpf(
+            lerpf(lerpf(at(0, 0, 0), at(1, 0, 0), u), lerpf(at(0, 1, 0), at(1, 1, 0), u), v),
+            lerpf(lerpf(at(0, 0, 1), at(1, 0, 1), u), lerpf(at(0, 1, 1), at(1, 1, 1), u), v),
+            w,
+        )
+    }
+
+    // distance to the nearest of a set of random points, one per lattice cell ("cellular" noise).
+    pub fn worley2(&self, x: f64, y: f64) -> f64 {
+        let (cx, cy) = (x.floor() as i64, y.floor() as i64);
+        let mut nearest = f64::MAX;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (ix, iy) = (cx + dx, cy + dy);
+                let h = self.hash2(ix, iy);
+                let px = ix as f64 + self.perm[h] as f64 / 256.;
+                let py = iy as f64 + self.perm[h + 1] as f64 / 256.;
+                nearest = nearest.min((px - x) * (px - x) + (py - y) * (py - y));
+            }
+        }
+        nearest.sqrt()
+    }
+
+    pub fn worley3(&self, p: &Vec3) -> f64 {
+        let (cx, cy, cz) = (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+        let mut nearest = f64::MAX;
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (ix, iy, iz) = (cx + dx, cy + dy, cz + dz);
+                    let h = self.hash3(ix, iy, iz);
+                    let feature = Vec3::new(
+                        ix as f64 + self.perm[h] as f64 / 256.,
+                        iy as f64 + self.perm[h + 1] as f64 / 256.,
+                        iz as f64 + self.perm[h + 2] as f64 / 256.,
+                    );
+                    nearest = nearest.min(feature.dist2(p));
+                }
+            }
+        }
+        nearest.sqrt()
+    }
+
+    // offsets a point by a smooth random vector field (https://iquilezles.org/articles/warp/),
+    // so sampling other noise at the warped point gives swirling, organic shapes.
+    pub fn warp2(&self, x: f64, y: f64, fbm: &Fbm, strength: f64) -> (f64, f64) {
+        let dx = fbm.sample2(|x, y| self.perlin2(x, y), x, y);
+        let dy = fbm.sample2(|x, y| self.perlin2(x, y), x + 5.2, y + 1.3);
+        (x + strength * dx, y + strength * dy)
+    }
+
+    pub fn warp3(&self, p: &Vec3, fbm: &Fbm, strength: f64) -> Vec3 {
+        let offset = Vec3::new(
+            fbm.sample3(|q| self.perlin3(q), p),
+            fbm.sample3(|q| self.perlin3(q), &p.clone().add_mut(5.2, 1.3, 2.8)),
+            fbm.sample3(|q| self.perlin3(q), &p.clone().add_mut(1.7, 9.2, 4.1)),
+        );
+        p.clone().sadd_vec_mut(strength, &offset)
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn grad2(hash: usize, x: f64, y: f64) -> f64 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn grad3(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::noise::*;
+
+    fn grid() -> Vec<Vec3> {
+        let mut points = vec![];
+        for i in 0..400 {
+            let i = i as f64;
+            points.push(Vec3::new(i * 0.173 - 20., i * 0.291 - 50., i * 0.057));
+        }
+        points
+    }
+
+    #[test]
+    fn deterministic() {
+        let a = Noise::new(7);
+        let b = Noise::new(7);
+        let c = Noise::new(8);
+        let p = Vec3::new(1.3, 2.7, -0.4);
+        assert_eq!(a.perlin3(&p), b.perlin3(&p));
+        assert_eq!(a.simplex2(0.3, 9.1), b.simplex2(0.3, 9.1));
+        assert_ne!(a.perlin3(&p), c.perlin3(&p));
+    }
+
+    #[test]
+    fn ranges() {
+        let noise = Noise::new(1);
+        for p in grid() {
+            for v in &[
+                noise.perlin2(p.x, p.y),
+                noise.perlin3(&p),
+                noise.simplex2(p.x, p.y),
+                noise.simplex3(&p),
+                noise.value2(p.x, p.y),
+                noise.value3(&p),
+                Fbm::default().sample3(|q| noise.perlin3(q), &p),
+            ] {
+                assert!(*v >= -1.05 && *v <= 1.05, "{} out of range at {}", v, p);
+            }
+            let w = noise.worley3(&p);
+            assert!(w >= 0. && w <= 3f64.sqrt() * 2.);
+            assert!(noise.worley2(p.x, p.y) >= 0.);
+        }
+    }
+
+    #[test]
+    fn gradient_noise_vanishes_on_lattice() {
+        let noise = Noise::new(3);
+        assert_eq!(0., noise.perlin2(4., -2.));
+        assert_eq!(0., noise.perlin3(&Vec3::new(1., 2., 3.)));
+    }
+
+    #[test]
+    fn perlin3_slope_is_bounded() {
+        let noise = Noise::new(2);
+        let step = 1e-3;
+        for p in grid() {
+            for d in &[Vec3::new(step, 0., 0.), Vec3::new(0., step, 0.), Vec3::new(0., 0., step)] {
+                let q = &p + d;
+                let slope = (noise.perlin3(&q) - noise.perlin3(&p)).abs() / step;
+                assert!(slope <= PERLIN3_SLOPE, "{} at {}", slope, p);
+            }
+        }
+    }
+
+    #[test]
+    fn continuous() {
+        let noise = Noise::new(5);
+        for p in grid() {
+            let q = p.clone().add_mut(1e-4, 1e-4, 1e-4);
+            assert!((noise.simplex3(&p) - noise.simplex3(&q)).abs() < 1e-2);
+            assert!((noise.perlin3(&p) - noise.perlin3(&q)).abs() < 1e-2);
+            let warped = noise.warp3(&p, &Fbm::default(), 0.5);
+            assert!(warped.dist2(&p) <= 0.5 * 0.5 * 3. * 1.1);
+        }
+    }
+}
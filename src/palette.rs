@@ -0,0 +1,132 @@
+use crate::color::Color;
+use crate::flower::Part;
+
+// a color ramp over [0, 1], interpolated perceptually (in oklab) between stops.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    stops: Vec<(f64, Color)>,
+}
+
+impl Gradient {
+    pub fn new(mut stops: Vec<(f64, Color)>) -> Self {
+        assert!(!stops.is_empty(), "gradient needs at least one stop.");
+        // total_cmp, so a NaN stop sorts to the end rather than panicking.
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    // evenly spaced stops from hex strings; only meant for the built-in palettes.
+    fn from_hexstrings(colors: &[&str]) -> Self {
+        let last = (colors.len() - 1).max(1) as f64;
+        Self::new(colors.iter()
+            .enumerate()
+            .map(|(i, hex)| (i as f64 / last, Color::from_hexstring(hex).unwrap()))
+            .collect())
+    }
+
+    pub fn sample(&self, t: f64) -> Color {
+        let first = &self.stops[0];
+        if t <= first.0 {
+            return first.1.clone();
+        }
+        for pair in self.stops.windows(2) {
+            let (s0, c0) = &pair[0];
+            let (s1, c1) = &pair[1];
+            if t <= *s1 {
+                return c0.clone().lerp_oklab((t - s0) / (s1 - s0), c1);
+            }
+        }
+        self.stops[self.stops.len() - 1].1.clone()
+    }
+}
+
+// the colors used for each part of the plant. every gradient is sampled by the parameter along
+// the part's curve: stems from base to tip, leaves from where they attach to their tip, petals
+// from the center of the bloom out to the rim.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub stem: Gradient,
+    pub leaf: Gradient,
+    pub petal: Gradient,
+}
+
+pub const PALETTE_NAMES: &[&str] = &["tea rose", "crimson", "sage leaf"];
+
+impl Palette {
+    pub fn named(name: &str) -> Option<Palette> {
+        let (stem, leaf, petal) = match name {
+            "tea rose" => (
+                ["#3d5a2e", "#6f8f45"],
+                ["#355e2c", "#6e9a4a", "#9fbf6a"],
+                ["#e88f7a", "#f4c2a8", "#fbe6d8"],
+            ),
+            "crimson" => (
+                ["#2a3f22", "#4f6f36"],
+                ["#243f22", "#4b7334", "#6e9147"],
+                ["#4a0613", "#a40e2c", "#dc143c"],
+            ),
+            "sage leaf" => (
+                ["#5b6b4f", "#8a9c76"],
+                ["#66785a", "#94a886", "#bccab0"],
+                ["#c9d6bd", "#e6eddc", "#fafcf6"],
+            ),
+            _ => return None,
+        };
+        Some(Palette {
+            name: name.to_string(),
+            stem: Gradient::from_hexstrings(&stem),
+            leaf: Gradient::from_hexstrings(&leaf),
+            petal: Gradient::from_hexstrings(&petal),
+        })
+    }
+
+    pub fn gradient(&self, part: Part) -> &Gradient {
+        match part {
+            Part::Stem => &self.stem,
+            Part::Leaf => &self.leaf,
+            Part::Petal => &self.petal,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::named(PALETTE_NAMES[0]).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::*;
+
+    #[test]
+    fn gradient_sample() {
+        let gradient = Gradient::new(vec![
+            (1., Color::white()),
+            (0., Color::from_hexstring("#ff0000").unwrap()),
+        ]);
+        assert_eq!("#ff0000", gradient.sample(-1.).as_hexstring());
+        assert_eq!("#ff0000", gradient.sample(0.).as_hexstring());
+        assert_eq!("#ffffff", gradient.sample(1.).as_hexstring());
+        assert_eq!("#ffffff", gradient.sample(2.).as_hexstring());
+        assert_ne!(gradient.sample(0.25).as_hexstring(), gradient.sample(0.75).as_hexstring());
+    }
+
+    #[test]
+    fn gradient_with_nan_stop() {
+        let gradient = Gradient::new(vec![
+            (f64::NAN, Color::white()),
+            (0., Color::from_hexstring("#ff0000").unwrap()),
+        ]);
+        assert_eq!("#ff0000", gradient.sample(0.).as_hexstring());
+    }
+
+    #[test]
+    fn named_palettes() {
+        for name in PALETTE_NAMES {
+            assert_eq!(*name, Palette::named(name).unwrap().name);
+        }
+        assert!(Palette::named("chartreuse").is_none());
+    }
+}
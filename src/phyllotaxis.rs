@@ -0,0 +1,104 @@
+use std::f64::consts::PI;
+
+// the angle between successive leaves on a spiral stem, which packs them so no leaf sits
+// directly over another: 2π / φ², about 137.5°.
+pub const GOLDEN_ANGLE: f64 = PI * 0.763_932_022_500_210_3;
+
+// how leaves are arranged around a stem.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phyllotaxis {
+    // one leaf per node, on alternating sides.
+    Alternate,
+    // two leaves per node, facing each other, with each pair a quarter turn from the last.
+    Opposite,
+    // one leaf per node, each a golden angle around from the last.
+    Spiral,
+}
+
+impl Phyllotaxis {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "alternate" => Some(Phyllotaxis::Alternate),
+            "opposite" => Some(Phyllotaxis::Opposite),
+            "spiral" => Some(Phyllotaxis::Spiral),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phyllotaxis::Alternate => "alternate",
+            Phyllotaxis::Opposite => "opposite",
+            Phyllotaxis::Spiral => "spiral",
+        }
+    }
+
+    // where `count` leaves go, as (parameter along the stem, angle around it in radians), for
+    // nodes spaced evenly between `start` and `end`.
+    pub fn placements(&self, count: usize, start: f64, end: f64) -> Vec<(f64, f64)> {
+        let per_node = match self {
+            Phyllotaxis::Opposite => 2,
+            _ => 1,
+        };
+        let nodes = count.div_ceil(per_node);
+        let node_s = |node: usize| if nodes > 1 {
+            start + (end - start) * node as f64 / (nodes - 1) as f64
+        } else {
+            start
+        };
+
+        (0..count)
+            .map(|i| {
+                let node = i / per_node;
+                let angle = match self {
+                    Phyllotaxis::Alternate => PI * node as f64,
+                    Phyllotaxis::Opposite => PI / 2. * node as f64 + PI * (i % 2) as f64,
+                    Phyllotaxis::Spiral => GOLDEN_ANGLE * node as f64,
+                };
+                (node_s(node), angle % (PI * 2.))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::phyllotaxis::*;
+
+    fn assert_close(expected: &[(f64, f64)], actual: &[(f64, f64)]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual) {
+            assert!((e.0 - a.0).abs() < 1e-9 && (e.1 - a.1).abs() < 1e-9, "{:?} != {:?}", e, a);
+        }
+    }
+
+    #[test]
+    fn alternate() {
+        assert_close(
+            &[(0.2, 0.), (0.4, PI), (0.6, 0.)],
+            &Phyllotaxis::Alternate.placements(3, 0.2, 0.6));
+    }
+
+    #[test]
+    fn opposite() {
+        assert_close(
+            &[(0.2, 0.), (0.2, PI), (0.6, PI / 2.), (0.6, PI * 1.5), (1., PI)],
+            &Phyllotaxis::Opposite.placements(5, 0.2, 1.));
+    }
+
+    #[test]
+    fn spiral() {
+        let placements = Phyllotaxis::Spiral.placements(4, 0., 0.3);
+        assert_close(&[(0., 0.), (0.1, GOLDEN_ANGLE)], &placements[..2]);
+        assert!((placements[3].1 - (GOLDEN_ANGLE * 3.) % (PI * 2.)).abs() < 1e-9);
+        assert!((GOLDEN_ANGLE.to_degrees() - 137.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn names() {
+        for p in &[Phyllotaxis::Alternate, Phyllotaxis::Opposite, Phyllotaxis::Spiral] {
+            assert_eq!(Some(*p), Phyllotaxis::from_name(p.name()));
+        }
+        assert_eq!(None, Phyllotaxis::from_name("whorled"));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::flower::fit_bezier3;
+use crate::threed::Vec3;
+use crate::utils::lerpf;
+
+// how many particles run along the stem. it's fit back to a cubic bezier through the particles
+// a third and two thirds of the way up, so it needs a multiple of three segments.
+const STEM_PARTICLES: usize = 7;
+
+// and along each branch, fit back to a quadratic through the middle one.
+const BRANCH_PARTICLES: usize = 5;
+
+// how hard a stem of thickness 1 springs back toward its rest shape at the base, per second
+// squared. it's springier toward the tip.
+const STIFFNESS: f64 = 15.;
+
+// how many times per step segment lengths are pulled back to their rest lengths.
+const ITERATIONS: usize = 4;
+
+// the longest step taken, in seconds, so a stalled frame doesn't throw the plant around.
+const MAX_STEP: f64 = 0.05;
+
+// below this much movement in a step, in pixels, the plant has settled.
+const SETTLED: f64 = 0.01;
+
+// a breeze blowing across the scene, steady with gusts rolling through it.
+#[derive(Clone, Debug)]
+pub struct Wind {
+    // which way it blows; only its direction matters.
+    pub direction: Vec3,
+    // how hard it pushes, in pixels per second squared.
+    pub strength: f64,
+    // how much the gusts vary it, as a fraction of `strength`: 0 is steady.
+    pub gustiness: f64,
+}
+
+impl Wind {
+    pub fn calm() -> Self {
+        Self {
+            direction: Vec3::right(),
+            strength: 0.,
+            gustiness: 0.,
+        }
+    }
+
+    // the push at `pt`, `time` seconds in. gusts travel downwind, so nearby parts sway together
+    // and distant ones out of step.
+    pub fn at(&self, pt: &Vec3, time: f64) -> Vec3 {
+        let direction = self.direction.unit();
+        let along = pt.dot(&direction) * 0.01;
+        let gust = (time * 1.7 - along).sin() * 0.6 + (time * 3.1 - along * 1.9).sin() * 0.4;
+        &direction * (self.strength * (1. + self.gustiness * gust))
+    }
+}
+
+// a chain of particles along a stem or branch, pinned at its base, with springs holding each
+// segment to its rest length and pulling each particle back toward its rest position. positions
+// are relative to the base.
+#[derive(Clone, Debug)]
+pub struct Rod {
+    rest: Vec<Vec3>,
+    points: Vec<Vec3>,
+    // where each particle was the step before, which is how fast it's moving.
+    previous: Vec<Vec3>,
+    // how hard each particle springs back to rest.
+    stiffness: Vec<f64>,
+}
+
+impl Rod {
+    // a rod at rest along `rest`, which starts at the origin. `stiffness` is at the base; the
+    // tip is a third as stiff.
+    pub fn new(rest: Vec<Vec3>, stiffness: f64) -> Self {
+        let count = rest.len();
+        let stiffness = (0..count)
+            .map(|i| stiffness * lerpf(1., 1. / 3., i as f64 / (count - 1).max(1) as f64))
+            .collect();
+        Self {
+            points: rest.clone(),
+            previous: rest.clone(),
+            rest,
+            stiffness,
+        }
+    }
+
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    // moves the shape it springs back to, leaving it where it is for now.
+    pub fn set_rest(&mut self, rest: Vec<Vec3>) {
+        self.rest = rest;
+    }
+
+    // advances `dt` seconds, with each particle pushed by `force` (an acceleration, given the
+    // particle's position). returns the furthest any particle moved.
+    pub fn step<F: Fn(&Vec3) -> Vec3>(&mut self, dt: f64, damping: f64, force: F) -> f64 {
+        let keep = (1. - damping * dt).max(0.);
+        for i in 1..self.points.len() {
+            let spring = &(&self.rest[i] - &self.points[i]) * self.stiffness[i];
+            let accel = &force(&self.points[i]) + &spring;
+            let velocity = &(&self.points[i] - &self.previous[i]) * keep;
+            self.previous[i] = self.points[i].clone();
+            self.points[i] = &(&self.points[i] + &velocity) + &(&accel * (dt * dt));
+        }
+
+        // springs can only bend it; it shouldn't stretch. the base stays put.
+        for _ in 0..ITERATIONS {
+            for i in 1..self.points.len() {
+                let length = self.rest[i].dist(&self.rest[i - 1]);
+                let span = &self.points[i] - &self.points[i - 1];
+                let error = &span * (1. - length / span.mag().max(1e-9));
+                if i == 1 {
+                    self.points[i] = &self.points[i] - &error;
+                } else {
+                    self.points[i - 1] = &self.points[i - 1] + &(&error * 0.5);
+                    self.points[i] = &self.points[i] - &(&error * 0.5);
+                }
+            }
+        }
+
+        self.points.iter()
+            .zip(&self.previous)
+            .map(|(p, q)| p.dist(q))
+            .fold(0., f64::max)
+    }
+}
+
+// where the stem's controls and each branch are pushed to after a step.
+pub struct Bent {
+    pub controls: Vec<Vec3>,
+    // for each branch, as from `Flower::branch_rests`, how far its middle and tip controls move.
+    pub bends: Vec<[Vec3; 2]>,
+    // whether anything is still moving.
+    pub moving: bool,
+}
+
+// the stem and branches as rods bending under gravity and wind. the plant as designed is their
+// rest shape, and they spring back toward it.
+pub struct Physics {
+    // downward pull, in pixels per second squared.
+    pub gravity: f64,
+    pub wind: Wind,
+    // how quickly swaying dies down, per second.
+    pub damping: f64,
+    stem: Option<Rod>,
+    branches: Vec<Rod>,
+    // seconds simulated so far.
+    time: f64,
+    last_tick: Option<f64>,
+}
+
+impl Physics {
+    pub fn new() -> Self {
+        Self {
+            gravity: 200.,
+            wind: Wind::calm(),
+            damping: 2.,
+            stem: None,
+            branches: vec![],
+            time: 0.,
+            last_tick: None,
+        }
+    }
+
+    // starts over from the plant at rest.
+    pub fn reset(&mut self) {
+        self.stem = None;
+        self.branches.clear();
+        self.last_tick = None;
+    }
+
+    // advances to `now`, in milliseconds, from the stem's rest controls and each branch's rest
+    // controls and thickness.
+    pub fn step(&mut self, now: f64, controls: &[Vec3], branches: &[([Vec3; 2], f64)]) -> Bent {
+        let dt = self.last_tick.map_or(0., |last| ((now - last) / 1000.).clamp(0., MAX_STEP));
+        self.last_tick = Some(now);
+        self.time += dt;
+
+        let base = controls[0].clone();
+        let stem_rest = (0..STEM_PARTICLES)
+            .map(|i| {
+                let s = i as f64 / (STEM_PARTICLES - 1) as f64;
+                &Vec3::bezier3(&controls[0], &controls[1], &controls[2], &controls[3], s) - &base
+            })
+            .collect();
+        let stem = match &mut self.stem {
+            Some(stem) => {
+                stem.set_rest(stem_rest);
+                stem
+            }
+            None => self.stem.insert(Rod::new(stem_rest, STIFFNESS * 4.)),
+        };
+
+        let (gravity, wind, time, damping) = (Vec3::new(0., self.gravity, 0.), &self.wind, self.time, self.damping);
+        let force = |pt: &Vec3| &gravity + &wind.at(&(&base + pt), time);
+        let mut moved = stem.step(dt, damping, force);
+        let points = stem.points();
+        let third = (STEM_PARTICLES - 1) / 3;
+        let controls = fit_bezier3(&points[0], &points[third], &points[third * 2], &points[STEM_PARTICLES - 1])
+            .into_iter()
+            .map(|p| &p + &base)
+            .collect();
+
+        if self.branches.len() != branches.len() {
+            self.branches = branches.iter()
+                .map(|(rest, thickness)| Rod::new(branch_rest(rest), STIFFNESS * thickness))
+                .collect();
+        }
+        let mut bends = vec![];
+        for (rod, (rest, _)) in self.branches.iter_mut().zip(branches) {
+            rod.set_rest(branch_rest(rest));
+            // gusts are felt as if the branch grew from the base of the stem rather than where it
+            // attaches, which is close enough for them to sway in step with it.
+            moved = moved.max(rod.step(dt, damping, force));
+            let points = rod.points();
+            let (mid, tip) = (&points[BRANCH_PARTICLES / 2], &points[BRANCH_PARTICLES - 1]);
+            // the quadratic through the base, `mid` halfway along and `tip`.
+            let control = &(mid * 2.) - &(tip * 0.5);
+            bends.push([&control - &rest[0], tip - &rest[1]]);
+        }
+
+        Bent {
+            controls,
+            bends,
+            moving: moved > SETTLED,
+        }
+    }
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// particles evenly along a branch's quadratic, from its base at the origin.
+fn branch_rest(controls: &[Vec3; 2]) -> Vec<Vec3> {
+    (0..BRANCH_PARTICLES)
+        .map(|i| Vec3::bezier2(&Vec3::zero(), &controls[0], &controls[1], i as f64 / (BRANCH_PARTICLES - 1) as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::*;
+
+    fn upright(height: f64) -> Vec<Vec3> {
+        (0..4).map(|i| Vec3::new(100., 500. - height * i as f64 / 3., 0.)).collect()
+    }
+
+    fn settle(physics: &mut Physics, controls: &[Vec3], branches: &[([Vec3; 2], f64)]) -> Bent {
+        let mut bent = physics.step(0., controls, branches);
+        for frame in 1..600 {
+            bent = physics.step(frame as f64 * 1000. / 60., controls, branches);
+        }
+        bent
+    }
+
+    #[test]
+    fn rests_without_forces() {
+        let mut physics = Physics::new();
+        physics.gravity = 0.;
+        let controls = upright(300.);
+        let bent = settle(&mut physics, &controls, &[([Vec3::new(40., -20., 0.), Vec3::new(80., 0., 0.)], 2.)]);
+        for (a, b) in bent.controls.iter().zip(&controls) {
+            assert!(a.dist(b) < 1e-6, "{} moved to {}", b, a);
+        }
+        assert!(bent.bends[0].iter().all(|b| b.mag() < 1e-6));
+        assert!(!bent.moving);
+    }
+
+    #[test]
+    fn droops_and_sways() {
+        let mut physics = Physics::new();
+        let controls = upright(300.);
+        let branch = ([Vec3::new(40., -20., 0.), Vec3::new(80., 0., 0.)], 2.);
+        let bent = settle(&mut physics, &controls, &[branch]);
+        // the branch sticks straight out, so it hangs down at the tip.
+        assert!(bent.bends[0][1].y > 1.);
+        assert!(!bent.moving);
+
+        physics.wind = Wind { direction: Vec3::right(), strength: 300., gustiness: 0. };
+        let bent = settle(&mut physics, &controls, &[]);
+        assert!(bent.controls[3].x > controls[3].x + 1.);
+        // it bends rather than stretches, and the base stays put.
+        assert_eq!(controls[0], bent.controls[0]);
+        assert!(bent.controls[3].dist(&controls[0]) <= 300. + 1e-6);
+    }
+
+    #[test]
+    fn gusts_vary() {
+        let wind = Wind { direction: Vec3::new(2., 0., 0.), strength: 10., gustiness: 0.5 };
+        let pushes: Vec<f64> = (0..20).map(|t| wind.at(&Vec3::zero(), t as f64 * 0.3).x).collect();
+        assert!(pushes.iter().all(|x| (5. ..=15.).contains(x)));
+        assert!(pushes.iter().any(|x| *x > 11.) && pushes.iter().any(|x| *x < 9.));
+        assert_eq!(0., wind.at(&Vec3::zero(), 1.).y);
+    }
+}
@@ -0,0 +1,179 @@
+// what a pointer is, as in a pointer event's `pointerType`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointerKind {
+    Mouse,
+    Pen,
+    Touch,
+}
+
+impl PointerKind {
+    pub fn from_name(name: &str) -> Option<PointerKind> {
+        match name {
+            "mouse" => Some(PointerKind::Mouse),
+            "pen" => Some(PointerKind::Pen),
+            "touch" => Some(PointerKind::Touch),
+            _ => None,
+        }
+    }
+}
+
+// the primary mouse button, pen contact or touch, in `Pointer::buttons`.
+pub const PRIMARY_BUTTON: u32 = 1;
+pub const SECONDARY_BUTTON: u32 = 2;
+pub const MIDDLE_BUTTON: u32 = 4;
+
+// keys held with a pointer, in `Pointer::modifiers`: shift is 1, alt 2, ctrl 4 and meta 8.
+pub const SHIFT_KEY: u32 = 1;
+pub const ALT_KEY: u32 = 2;
+
+// one mouse, pen or finger, where it is on the canvas, as reported by a pointer event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pointer {
+    pub id: i32,
+    pub kind: PointerKind,
+    pub x: f64,
+    pub y: f64,
+    // in [0, 1]; 0.5 for mice and touches that can't tell.
+    pub pressure: f64,
+    // a bit for each button held, as in a pointer event's `buttons`.
+    pub buttons: u32,
+    // a bit for each modifier key held.
+    pub modifiers: u32,
+}
+
+impl Pointer {
+    pub fn is_pressed(&self, button: u32) -> bool {
+        self.buttons & button != 0
+    }
+
+    pub fn has_modifier(&self, modifier: u32) -> bool {
+        self.modifiers & modifier != 0
+    }
+}
+
+// how two fingers moved since the last time either did: `scale` is how much further apart
+// they are, and dx, dy how far the point between them moved. that point was at x, y.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pinch {
+    pub x: f64,
+    pub y: f64,
+    pub scale: f64,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+// every pointer that's down on the canvas. the first one down is the primary pointer, which
+// edits; once a second finger joins, the two of them pinch and pan instead.
+pub struct Pointers {
+    down: Vec<Pointer>,
+    // whether the pointers down are gesturing, which lasts until they're all lifted so lifting
+    // one finger of a pinch doesn't suddenly start dragging with the other.
+    gesturing: bool,
+}
+
+impl Pointers {
+    pub fn new() -> Self {
+        Self {
+            down: vec![],
+            gesturing: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.down.is_empty()
+    }
+
+    // whether `id` is the one pointer editing.
+    pub fn is_primary(&self, id: i32) -> bool {
+        !self.gesturing && self.down.first().is_some_and(|p| p.id == id)
+    }
+
+    // a pointer pressed down. returns whether it started a gesture.
+    pub fn press(&mut self, pointer: Pointer) -> bool {
+        self.down.retain(|p| p.id != pointer.id);
+        self.down.push(pointer);
+        let started = !self.gesturing && self.down.len() == 2;
+        self.gesturing |= self.down.len() >= 2;
+        started
+    }
+
+    // a pointer moved, down or not. while gesturing, returns how the first two fingers pinched.
+    pub fn moved(&mut self, pointer: Pointer) -> Option<Pinch> {
+        let i = self.down.iter().position(|p| p.id == pointer.id)?;
+        if !self.gesturing || i >= 2 {
+            self.down[i] = pointer;
+            return None;
+        }
+        let (before_center, before_span) = span(&self.down[0], &self.down[1]);
+        self.down[i] = pointer;
+        let (center, span) = span(&self.down[0], &self.down[1]);
+        Some(Pinch {
+            x: before_center.0,
+            y: before_center.1,
+            scale: if before_span > 0. { span / before_span } else { 1. },
+            dx: center.0 - before_center.0,
+            dy: center.1 - before_center.1,
+        })
+    }
+
+    // a pointer lifted, or cancelled.
+    pub fn release(&mut self, id: i32) {
+        self.down.retain(|p| p.id != id);
+        if self.down.is_empty() {
+            self.gesturing = false;
+        }
+    }
+}
+
+impl Default for Pointers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the point halfway between two pointers, and how far apart they are.
+fn span(a: &Pointer, b: &Pointer) -> ((f64, f64), f64) {
+    let center = ((a.x + b.x) / 2., (a.y + b.y) / 2.);
+    (center, (a.x - b.x).hypot(a.y - b.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pointer::*;
+
+    fn touch(id: i32, x: f64, y: f64) -> Pointer {
+        Pointer { id, kind: PointerKind::Touch, x, y, pressure: 0.5, buttons: PRIMARY_BUTTON, modifiers: 0 }
+    }
+
+    #[test]
+    fn first_pointer_edits() {
+        let mut pointers = Pointers::new();
+        assert!(!pointers.press(touch(3, 0., 0.)));
+        assert!(pointers.is_primary(3));
+        assert_eq!(None, pointers.moved(touch(3, 5., 5.)));
+        // pointers that aren't down, like a hovering mouse, don't gesture either.
+        assert_eq!(None, pointers.moved(touch(4, 5., 5.)));
+        pointers.release(3);
+        assert!(!pointers.is_primary(3));
+    }
+
+    #[test]
+    fn two_fingers_pinch() {
+        let mut pointers = Pointers::new();
+        pointers.press(touch(1, 0., 0.));
+        assert!(pointers.press(touch(2, 100., 0.)));
+        assert!(!pointers.is_primary(1));
+
+        let pinch = pointers.moved(touch(2, 200., 0.)).unwrap();
+        assert_eq!(Pinch { x: 50., y: 0., scale: 2., dx: 50., dy: 0. }, pinch);
+        let pan = pointers.moved(touch(1, 0., 20.)).unwrap();
+        assert_eq!((0., 10.), (pan.dx, pan.dy));
+
+        // lifting one finger doesn't go back to editing until both are up.
+        pointers.release(2);
+        assert!(!pointers.is_primary(1));
+        pointers.release(1);
+        pointers.press(touch(1, 0., 0.));
+        assert!(pointers.is_primary(1));
+    }
+}
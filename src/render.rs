@@ -0,0 +1,551 @@
+use wasm_bindgen::JsCast;
+
+use crate::color::Color;
+use crate::flower::{Flower, Material, Part};
+use crate::noise::{Fbm, Noise};
+use crate::palette::Palette;
+use crate::sdf::{raycast, screen_bounds};
+use crate::sdf_cache::SdfCache;
+use crate::threed::{Aabb, Ray, Vec3};
+use crate::tiles::TileQueue;
+use crate::utils::current_time_millis;
+use crate::viewport::{Rect, RenderRegion, View};
+use crate::watercolor;
+
+// rays are cast orthographically down +z, starting this far in front of the nearest part of
+// the plant and stopping this far behind the furthest.
+const DEPTH_MARGIN: f64 = 10.;
+
+// width of the black silhouette drawn around the plant.
+const OUTLINE_WIDTH: f64 = 2.;
+
+// size of the squares sampled when searching for the plant's bounding box.
+const BOUNDS_CELL_SIZE: f64 = 16.;
+
+const TILE_SIZE: usize = 32;
+
+// spacing and brick width (in voxels) of the optional distance field cache.
+const CACHE_VOXEL_SIZE: f64 = 2.;
+const CACHE_BRICK_VOXELS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderMode {
+    Shaded,
+    Watercolor,
+}
+
+impl RenderMode {
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        match name {
+            "shaded" => Some(RenderMode::Shaded),
+            "watercolor" => Some(RenderMode::Watercolor),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Shaded => "shaded",
+            RenderMode::Watercolor => "watercolor",
+        }
+    }
+}
+
+// the surface seen through a pixel, in the scene rather than on screen.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub material: Material,
+}
+
+#[derive(Clone, Debug)]
+pub enum Sample {
+    Empty,
+    // just outside the surface, where the silhouette line is drawn.
+    Outline,
+    Surface(Fragment),
+}
+
+// what the raymarcher found at every pixel. coloring is done from this in a separate pass, so
+// that stylized modes can look at neighboring pixels, and so that changing colors or modes
+// doesn't mean marching every ray again.
+pub struct GBuffer {
+    width: usize,
+    height: usize,
+    samples: Vec<Sample>,
+}
+
+impl GBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            samples: vec![Sample::Empty; width * height],
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> &Sample {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return &Sample::Empty;
+        }
+        &self.samples[y as usize * self.width + x as usize]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, sample: Sample) {
+        self.samples[y * self.width + x] = sample;
+    }
+}
+
+// renders the plant progressively into an offscreen buffer, one tile at a time, in two passes:
+// rays are marched into the gbuffer, then tiles are composed from it into pixels. the buffer
+// keeps its pixels between frames, so after an edit only the tiles that could have changed are
+// re-rendered.
+pub struct Renderer {
+    buffer: web_sys::HtmlCanvasElement,
+    g: web_sys::CanvasRenderingContext2d,
+    width: f64,
+    height: f64,
+    background: Color,
+    palette: Palette,
+    mode: RenderMode,
+    rect: Rect,
+    // rays are marched on screen, through the view, so zooming in renders finer detail rather
+    // than bigger pixels.
+    view: View,
+    gbuffer: GBuffer,
+    // tiles waiting to be raymarched into the gbuffer.
+    tiles: TileQueue,
+    // tiles waiting to be composed from the gbuffer.
+    compose: TileQueue,
+    // the tile being raymarched, and how many of its pixels are done.
+    current: Option<(Rect, usize)>,
+    cache: Option<SdfCache>,
+    // the depths rays are cast between, from `depth_range`.
+    depth: (f64, f64),
+    // for surface texture and paper.
+    noise: Noise,
+}
+
+impl Renderer {
+    pub fn new(document: &web_sys::Document) -> Self {
+        let buffer = document
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .unwrap();
+        let g = buffer
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()
+            .unwrap();
+
+        Self {
+            buffer,
+            g,
+            width: 0.,
+            height: 0.,
+            background: Color::white(),
+            palette: Palette::default(),
+            mode: RenderMode::Shaded,
+            rect: Rect::empty(),
+            view: View::identity(),
+            gbuffer: GBuffer::new(0, 0),
+            tiles: TileQueue::new(0., 0., TILE_SIZE),
+            compose: TileQueue::new(0., 0., TILE_SIZE),
+            current: None,
+            cache: None,
+            depth: depth_range(&[]),
+            noise: Noise::new(0),
+        }
+    }
+
+    // returns whether the size actually changed.
+    pub fn resize(&mut self, width: f64, height: f64) -> bool {
+        if width == self.width && height == self.height {
+            return false;
+        }
+        self.width = width;
+        self.height = height;
+        self.buffer.set_width(width as u32);
+        self.buffer.set_height(height as u32);
+        self.gbuffer = GBuffer::new(width as usize, height as usize);
+        self.tiles = TileQueue::new(width, height, TILE_SIZE);
+        self.compose = TileQueue::new(width, height, TILE_SIZE);
+        self.current = None;
+        self.invalidate_all();
+        true
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.current.is_none() && self.tiles.is_empty() && self.compose.is_empty()
+    }
+
+    pub fn invalidate(&mut self, rect: &Rect) {
+        self.tiles.mark(rect);
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.tiles.mark_all();
+        if let Some(cache) = &mut self.cache {
+            cache.clear();
+        }
+    }
+
+    // queues the screen area covered by a part of the plant.
+    pub fn invalidate_bounds(&mut self, bounds: &Aabb) {
+        if let Some(cache) = &mut self.cache {
+            cache.invalidate(bounds);
+        }
+        let (min, max) = (self.view.to_screen(&bounds.min), self.view.to_screen(&bounds.max));
+        let rect = Rect::from_corners(min.x, min.y, max.x, max.y);
+        // the outline reaches a little past the surface.
+        self.invalidate(&rect.to_pixels().expand(OUTLINE_WIDTH + 2.));
+    }
+
+    // moves where rays start and stop to just take in everything within `bounds`, so parts of
+    // the plant reaching toward or away from the viewer aren't cut off.
+    pub fn fit_depth(&mut self, bounds: &[Aabb]) {
+        self.depth = depth_range(bounds);
+    }
+
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    // everything on screen moves, but the sdf cache is in the scene, so it stays.
+    pub fn set_view(&mut self, view: View) {
+        if view == self.view {
+            return;
+        }
+        self.view = view;
+        self.tiles.mark_all();
+    }
+
+    pub fn set_background(&mut self, color: Color) {
+        if color == self.background {
+            return;
+        }
+        self.background = color;
+        self.compose.mark_all();
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.compose.mark_all();
+    }
+
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        if mode == self.mode {
+            return;
+        }
+        self.mode = mode;
+        self.compose.mark_all();
+    }
+
+    pub fn set_sdf_cache(&mut self, enabled: bool) {
+        if enabled == self.cache.is_some() {
+            return;
+        }
+        self.cache = if enabled {
+            // the outline is found by offsetting the surface, so it needs exact values too.
+            Some(SdfCache::new(CACHE_VOXEL_SIZE, CACHE_BRICK_VOXELS, OUTLINE_WIDTH + 1.))
+        } else {
+            None
+        };
+    }
+
+    pub fn resolve_rect(&self, region: &RenderRegion, flower: &Flower) -> Rect {
+        let canvas_rect = Rect::new(0., 0., self.width, self.height);
+        let rect = match region {
+            RenderRegion::FullCanvas => canvas_rect.clone(),
+            RenderRegion::PlantBounds => screen_bounds(
+                &canvas_rect,
+                BOUNDS_CELL_SIZE,
+                self.camera_z(),
+                self.ray_length(),
+                &|s| self.screen_distance(flower, s) - OUTLINE_WIDTH,
+            ).unwrap_or_else(Rect::empty),
+            RenderRegion::Crop(crop) => crop.clone(),
+        };
+        rect.to_pixels().intersection(&canvas_rect)
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        if rect == self.rect {
+            return;
+        }
+        // only tiles whose coverage changed need to be repainted; pixels outside the region are
+        // painted as background.
+        let old = self.rect.clone();
+        self.tiles.mark_where(|tile| tile.intersection(&old) != tile.intersection(&rect));
+        self.rect = rect;
+    }
+
+    // renders queued tiles until the deadline passes.
+    pub fn render(&mut self, flower: &Flower, deadline: f64) {
+        while current_time_millis() < deadline {
+            // shaded tiles only depend on their own pixels, so they can be shown as soon as
+            // they're marched. watercolor looks at neighbors too, so it waits for every pending
+            // tile to be marched first.
+            let gbuffer_ready = self.current.is_none() && self.tiles.is_empty();
+            if self.mode == RenderMode::Shaded || gbuffer_ready {
+                if let Some(tile) = self.compose.pop() {
+                    self.compose_tile(&tile);
+                    continue;
+                }
+            }
+
+            let (tile, done) = match self.current.take() {
+                Some(current) => current,
+                None => match self.tiles.pop() {
+                    Some(tile) => (tile, 0),
+                    None => return,
+                },
+            };
+
+            let columns = tile.width as usize;
+            let x = tile.x + (done % columns) as f64;
+            let y = tile.y + (done / columns) as f64;
+            let sample = if self.rect.contains(x, y) {
+                self.sample_pixel(flower, x, y)
+            } else {
+                Sample::Empty
+            };
+            self.gbuffer.set(x as usize, y as usize, sample);
+
+            if done + 1 < tile.pixel_count() {
+                self.current = Some((tile, done + 1));
+            } else {
+                // watercolor edges and bleeding reach into neighboring tiles.
+                self.compose.mark(&tile.expand(watercolor::RADIUS as f64));
+            }
+        }
+    }
+
+    pub fn blit(&self, g: &web_sys::CanvasRenderingContext2d) {
+        g.draw_image_with_html_canvas_element(&self.buffer, 0., 0.).unwrap();
+    }
+
+    // everything rendered so far, as rgba rows.
+    pub fn pixels(&self) -> Vec<u8> {
+        self.g.get_image_data(0., 0., self.width, self.height)
+            .unwrap()
+            .data()
+            .to_vec()
+    }
+
+    // the point on the plant under x, y on screen, in the scene, if there is one.
+    pub fn pick(&self, flower: &Flower, x: f64, y: f64) -> Option<Vec3> {
+        raycast(&self.ray(x, y), self.ray_length(), &|s| self.screen_distance(flower, s))
+            .map(|hit| self.view.to_scene(&hit.point))
+    }
+
+    fn sample_pixel(&self, flower: &Flower, x: f64, y: f64) -> Sample {
+        let ray = self.ray(x, y);
+
+        if let Some(hit) = raycast(&ray, self.ray_length(), &|s| self.screen_distance(flower, s)) {
+            let point = self.view.to_scene(&hit.point);
+            let material = flower.material(&point);
+            return Sample::Surface(Fragment {
+                point,
+                normal: hit.normal,
+                material,
+            });
+        }
+
+        // TODO anti-alias.
+        if raycast(&ray, self.ray_length(), &|s| self.screen_distance(flower, s) - OUTLINE_WIDTH).is_some() {
+            return Sample::Outline;
+        }
+
+        Sample::Empty
+    }
+
+    fn compose_tile(&self, tile: &Rect) {
+        for y in (tile.y as i64)..(tile.bottom() as i64) {
+            for x in (tile.x as i64)..(tile.right() as i64) {
+                let color = match self.mode {
+                    RenderMode::Shaded => match self.gbuffer.get(x, y) {
+                        Sample::Empty => self.background.clone(),
+                        Sample::Outline => Color::black(),
+                        Sample::Surface(fragment) => self.shade(fragment),
+                    },
+                    RenderMode::Watercolor => watercolor::stylize(
+                        &self.gbuffer,
+                        x,
+                        y,
+                        &self.background,
+                        &self.noise,
+                        &|fragment| self.pigment(fragment),
+                    ),
+                };
+                let (x, y) = (x as f64, y as f64);
+                if color.alpha() < 1. {
+                    // don't let translucent pixels blend with whatever was rendered here before.
+                    self.g.clear_rect(x, y, 1., 1.);
+                }
+                self.set_fill_color(&color);
+                self.g.fill_rect(x, y, 1., 1.);
+            }
+        }
+    }
+
+    // fixed on screen, so the plant is lit the same however it's zoomed.
+    fn light_pos(&self) -> Vec3 {
+        self.view.to_scene(&Vec3::new(
+            self.width * 0.75,
+            self.height / 2.,
+            -self.width * 0.25,
+        ))
+    }
+
+    fn base_color(&self, fragment: &Fragment) -> Color {
+        let material = &fragment.material;
+        let color = self.palette.gradient(material.part).sample(material.s);
+        color.scale(1. - 0.15 * self.surface_detail(fragment))
+    }
+
+    // how much darker fine surface detail makes a point, in [0, 1]: thin branching veins on
+    // leaves, long streaks and scattered pores (lenticels) on stems, and soft mottling on petals.
+    fn surface_detail(&self, fragment: &Fragment) -> f64 {
+        let p = &fragment.point;
+        match fragment.material.part {
+            Part::Leaf => {
+                let warped = self.noise.warp3(&(p * 0.08), &Fbm::default(), 0.6);
+                let ridge = 1. - self.noise.perlin3(&(&warped * 3.)).abs();
+                ridge.powi(8)
+            }
+            Part::Stem => {
+                let streaks = 0.5 + 0.5 * self.noise.simplex3(&Vec3::new(p.x / 2., p.y / 30., p.z / 2.));
+                let pores = (1. - 4. * self.noise.worley3(&(p * 0.25))).max(0.);
+                (0.6 * streaks + pores).min(1.)
+            }
+            Part::Petal => {
+                0.5 + 0.5 * Fbm::default().sample3(|q| self.noise.value3(q), &(p * 0.15))
+            }
+        }
+    }
+
+    fn shade(&self, fragment: &Fragment) -> Color {
+        let light_dir = (&self.light_pos() - &fragment.point).unit();
+        let diffuse = fragment.normal.dot(&light_dir).max(0.);
+        let ambient = 0.2;
+        self.base_color(fragment).scale(ambient + diffuse)
+    }
+
+    // watercolor washes are much flatter than lit surfaces; light only gently modulates the
+    // pigment.
+    fn pigment(&self, fragment: &Fragment) -> Color {
+        let light_dir = (&self.light_pos() - &fragment.point).unit();
+        let diffuse = fragment.normal.dot(&light_dir).max(0.);
+        self.base_color(fragment).scale(0.75 + 0.25 * diffuse)
+    }
+
+    // the ray through x, y on screen. rays start at the same depth in the scene however it's
+    // zoomed, so zooming in doesn't move the camera into the plant.
+    fn ray(&self, x: f64, y: f64) -> Ray {
+        Ray::new(Vec3::new(x, y, self.camera_z()), Vec3::forward())
+    }
+
+    // where rays start and how far they go, in screen space.
+    fn camera_z(&self) -> f64 {
+        self.view.to_screen(&Vec3::new(0., 0., self.depth.0)).z
+    }
+
+    fn ray_length(&self) -> f64 {
+        (self.depth.1 - self.depth.0) * self.view.scale
+    }
+
+    fn distance(&self, flower: &Flower, pt: &Vec3) -> f64 {
+        match &self.cache {
+            Some(cache) => cache.distance(pt, &|p| flower.distance(p)),
+            None => flower.distance(pt),
+        }
+    }
+
+    // the distance to the plant from a point on screen, in screen pixels. the view only scales
+    // and moves the scene, so distances scale with it.
+    fn screen_distance(&self, flower: &Flower, pt: &Vec3) -> f64 {
+        self.distance(flower, &self.view.to_scene(pt)) * self.view.scale
+    }
+
+    fn set_fill_color(&self, color: &Color) {
+        self.g.set_fill_style_str(&color.as_hexstring())
+    }
+}
+
+// the depths, near and far, that rays are cast between to see everything within `bounds`.
+pub fn depth_range(bounds: &[Aabb]) -> (f64, f64) {
+    let near = bounds.iter().map(|b| b.min.z).fold(f64::INFINITY, f64::min);
+    let far = bounds.iter().map(|b| b.max.z).fold(f64::NEG_INFINITY, f64::max);
+    if near > far {
+        return (-DEPTH_MARGIN, DEPTH_MARGIN);
+    }
+    (near - DEPTH_MARGIN, far + DEPTH_MARGIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flower::LeafArrangement;
+    use crate::phyllotaxis::Phyllotaxis;
+    use crate::render::*;
+
+    // checks the whole plant is between the depths rays are cast between, and that no ray
+    // starts inside it, by sampling the plane rays start from across the plant.
+    fn assert_in_view(flower: &Flower) {
+        let bounds = flower.bounds();
+        let (near, far) = depth_range(&bounds);
+        let all = bounds.iter().skip(1).fold(bounds[0].clone(), |a, b| a.union(b));
+        assert!(near < all.min.z && all.max.z < far, "{} to {} doesn't cover {:?}", near, far, all);
+        let mut y = all.min.y;
+        while y <= all.max.y {
+            let mut x = all.min.x;
+            while x <= all.max.x {
+                let start = Vec3::new(x, y, near);
+                assert!(flower.distance(&start) > 0., "a ray starts inside the plant at {}", start);
+                x += 8.;
+            }
+            y += 8.;
+        }
+    }
+
+    #[test]
+    fn camera_sees_default_and_seeded_plants() {
+        // the bloom on the default plant reaches well in front of the stem.
+        assert_in_view(&Flower::new());
+        for seed in 0..4 {
+            assert_in_view(&Flower::from_seed(seed, 800., 800.));
+        }
+    }
+
+    #[test]
+    fn camera_sees_arranged_leaves() {
+        // arranged leaves spread out toward and away from the viewer.
+        for pattern in &[Phyllotaxis::Spiral, Phyllotaxis::Alternate, Phyllotaxis::Opposite] {
+            let mut flower = Flower::new();
+            flower.set_arrangement(LeafArrangement::new(*pattern, 6));
+            assert_in_view(&flower);
+        }
+    }
+
+    #[test]
+    fn camera_sees_handles_moved_in_depth() {
+        let mut flower = Flower::new();
+        let mut controls = flower.controls().clone();
+        controls[1].z = -60.;
+        controls[2].z = -100.;
+        controls[3].z = 120.;
+        flower.update_controls(&controls);
+        assert_in_view(&flower);
+    }
+}
@@ -1,7 +1,7 @@
 use wasm_bindgen::prelude::*;
 
-use crate::threed::{Ray, Vec3};
-use js_sys::Math::{max, sqrt};
+use crate::threed::{Aabb, Ray, Vec3};
+use crate::viewport::Rect;
 
 #[wasm_bindgen]
 extern "C" {
@@ -15,9 +15,7 @@ pub struct RayHit {
     pub normal: Vec3,
 }
 
-fn raymarch<S: Fn(&Vec3) -> f64>(ray: &Ray, maxdist: f64, scene: &S) -> Option<f64> {
-    let eps = 0.001;
-
+fn raymarch<S: Fn(&Vec3) -> f64>(ray: &Ray, maxdist: f64, eps: f64, scene: &S) -> Option<f64> {
     let mut traveled = 0.;
     let mut sd = scene(&ray.origin);
     while sd > eps && traveled < maxdist {
@@ -33,7 +31,7 @@ fn raymarch<S: Fn(&Vec3) -> f64>(ray: &Ray, maxdist: f64, scene: &S) -> Option<f
 }
 
 pub fn raycast<S: Fn(&Vec3) -> f64>(ray: &Ray, maxdist: f64, scene: &S) -> Option<RayHit> {
-    let distance = raymarch(ray, maxdist, scene);
+    let distance = raymarch(ray, maxdist, 0.001, scene);
     if distance.is_none() {
         return None;
     }
@@ -57,6 +55,39 @@ pub fn raycast<S: Fn(&Vec3) -> f64>(ray: &Ray, maxdist: f64, scene: &S) -> Optio
     })
 }
 
+// finds the bounding box of everything in the scene that is visible to an orthographic camera
+// looking down +z from `near`, by marching one ray through the center of each `cell`-sized
+// square of `area`. a ray counts as a hit once it gets within half a cell diagonal of the
+// surface, so thin features between ray centers aren't missed.
+pub fn screen_bounds<S: Fn(&Vec3) -> f64>(
+    area: &Rect,
+    cell: f64,
+    near: f64,
+    maxdist: f64,
+    scene: &S) -> Option<Rect> {
+    let eps = cell * 0.75;
+    let columns = (area.width / cell).ceil() as usize;
+    let rows = (area.height / cell).ceil() as usize;
+
+    let mut bounds: Option<Rect> = None;
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = area.x + (column as f64 + 0.5) * cell;
+            let y = area.y + (row as f64 + 0.5) * cell;
+            let ray = Ray::new(Vec3::new(x, y, near), Vec3::forward());
+            if raymarch(&ray, maxdist, eps, scene).is_some() {
+                let hit = Rect::new(x - cell / 2., y - cell / 2., cell, cell);
+                bounds = Some(match bounds {
+                    None => hit,
+                    Some(b) => b.union(&hit),
+                });
+            }
+        }
+    }
+
+    bounds.map(|b| b.intersection(area))
+}
+
 pub fn find_closest_point<F: Fn(f64) -> Vec3>(point: &Vec3, curve: F) -> f64 {
     // approximate the closest point by sampling uniformly along the curve
     // this is more than enough samples to accurately approximate the closest point for a cubic
@@ -131,7 +162,7 @@ pub fn sdf_curve<C: Fn(f64) -> Vec3, T: Fn(f64) -> f64>(
             Some(f) => if f > b.unwrap() { f } else { b.unwrap() }
         }))
         .unwrap();
-    let radius = sqrt(radius2);
+    let radius = radius2.sqrt();
     if centroid.dist(pt) > radius * 1.05 {
         return centroid.dist(pt) - radius;
     }
@@ -140,6 +171,17 @@ pub fn sdf_curve<C: Fn(f64) -> Vec3, T: Fn(f64) -> f64>(
     curve(s).dist(pt) - thickness(s)
 }
 
+// a bounding box of the tube described by `sdf_curve`, given the largest thickness along it.
+pub fn curve_bounds<C: Fn(f64) -> Vec3>(curve: &C, max_thickness: f64) -> Aabb {
+    let samples = 20;
+    let mut bounds = Aabb::from_point(&curve(0.));
+    for i in 1..samples {
+        bounds = bounds.include(&curve(i as f64 / (samples - 1) as f64));
+    }
+    // samples can cut corners on tight bends, so pad a little past the thickness.
+    bounds.expand(max_thickness + 1.)
+}
+
 pub fn sdf_sphere(origin: Vec3, radius: f64) -> Box<dyn Fn(&Vec3) -> f64> {
     Box::new(move |pt: &Vec3| pt.dist(&origin) - radius)
 }
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::threed::{Aabb, Vec3};
+
+type BrickKey = (i64, i64, i64);
+
+enum Brick {
+    // every sample is at least `band` from the surface.
+    Empty,
+    // (voxels + 1)^3 samples, x-major, clamped to `band`.
+    Samples(Vec<f64>),
+}
+
+// caches a scene sdf on a sparse grid of bricks, each holding a small block of samples. bricks
+// are built lazily the first time a query lands in them, and trilinearly interpolated after
+// that. close to the surface, where interpolation isn't accurate enough to find hits and
+// normals, queries fall through to the exact scene.
+//
+// distances are truncated to `band` (the width of one brick), so a brick only depends on the
+// geometry within one brick-width of it, and an edit only invalidates bricks near the parts
+// that changed.
+pub struct SdfCache {
+    voxel: f64,
+    voxels: usize,
+    band: f64,
+    exact_below: f64,
+    bricks: RefCell<HashMap<BrickKey, Brick>>,
+}
+
+impl SdfCache {
+    pub fn new(voxel: f64, voxels: usize, exact_below: f64) -> Self {
+        Self {
+            voxel,
+            voxels,
+            band: voxel * voxels as f64,
+            exact_below,
+            bricks: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn distance<S: Fn(&Vec3) -> f64>(&self, pt: &Vec3, scene: &S) -> f64 {
+        let key = self.brick_key(pt);
+        let mut bricks = self.bricks.borrow_mut();
+        let brick = bricks.entry(key).or_insert_with(|| self.build_brick(key, scene));
+
+        let samples = match brick {
+            Brick::Empty => return self.band,
+            Brick::Samples(samples) => samples,
+        };
+
+        let origin = self.brick_origin(key);
+        let local = (pt - &origin).scale_uniform_mut(1. / self.voxel);
+        let d = self.trilinear(samples, &local);
+
+        if d < self.exact_below + self.voxel {
+            drop(bricks);
+            return scene(pt);
+        }
+
+        // interpolated values can overshoot the true distance by up to half a voxel diagonal.
+        d - self.voxel * 0.87
+    }
+
+    // drops every brick whose samples could have seen geometry inside `bounds`.
+    pub fn invalidate(&mut self, bounds: &Aabb) {
+        let reach = bounds.expand(self.band);
+        let lo = self.brick_key(&reach.min);
+        let hi = self.brick_key(&reach.max);
+        self.bricks.get_mut().retain(|key, _| {
+            key.0 < lo.0 || key.0 > hi.0
+                || key.1 < lo.1 || key.1 > hi.1
+                || key.2 < lo.2 || key.2 > hi.2
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.bricks.get_mut().clear();
+    }
+
+    fn brick_key(&self, pt: &Vec3) -> BrickKey {
+        (
+            (pt.x / self.band).floor() as i64,
+            (pt.y / self.band).floor() as i64,
+            (pt.z / self.band).floor() as i64,
+        )
+    }
+
+    fn brick_origin(&self, key: BrickKey) -> Vec3 {
+        Vec3::new(key.0 as f64, key.1 as f64, key.2 as f64).scale_uniform_mut(self.band)
+    }
+
+    fn build_brick<S: Fn(&Vec3) -> f64>(&self, key: BrickKey, scene: &S) -> Brick {
+        let origin = self.brick_origin(key);
+        let half = self.band / 2.;
+        let center = origin.clone().add_mut(half, half, half);
+
+        // a brick whose center is far enough away can't have any sample inside the band.
+        let half_diagonal = half * 3f64.sqrt();
+        if scene(&center) - half_diagonal >= self.band {
+            return Brick::Empty;
+        }
+
+        let n = self.voxels + 1;
+        let mut samples = Vec::with_capacity(n * n * n);
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    let pt = origin.clone().add_mut(
+                        i as f64 * self.voxel,
+                        j as f64 * self.voxel,
+                        k as f64 * self.voxel,
+                    );
+                    samples.push(scene(&pt).min(self.band));
+                }
+            }
+        }
+        Brick::Samples(samples)
+    }
+
+    fn trilinear(&self, samples: &[f64], local: &Vec3) -> f64 {
+        let n = self.voxels + 1;
+        let max = (self.voxels - 1) as f64;
+        let (x0, y0, z0) = (
+            local.x.floor().max(0.).min(max),
+            local.y.floor().max(0.).min(max),
+            local.z.floor().max(0.).min(max),
+        );
+        let (tx, ty, tz) = (local.x - x0, local.y - y0, local.z - z0);
+        let (i, j, k) = (x0 as usize, y0 as usize, z0 as usize);
+        let at = |di: usize, dj: usize, dk: usize| samples[((i + di) * n + (j + dj)) * n + (k + dk)];
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let x00 = lerp(at(0, 0, 0), at(1, 0, 0), tx);
+        let x10 = lerp(at(0, 1, 0), at(1, 1, 0), tx);
+        let x01 = lerp(at(0, 0, 1), at(1, 0, 1), tx);
+        let x11 = lerp(at(0, 1, 1), at(1, 1, 1), tx);
+        lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sdf_cache::*;
+
+    fn sphere(pt: &Vec3) -> f64 {
+        (pt.x * pt.x + pt.y * pt.y + pt.z * pt.z).sqrt() - 10.
+    }
+
+    #[test]
+    fn conservative_away_from_surface() {
+        let cache = SdfCache::new(2., 8, 1.);
+        for pt in &[Vec3::new(13., 1., 0.), Vec3::new(0., -15.5, 3.), Vec3::new(4., 4., 4.)] {
+            let d = cache.distance(pt, &sphere);
+            assert!(d <= sphere(pt), "{} overestimates at {}", d, pt);
+            assert!(d >= sphere(pt) - 4., "{} is too loose at {}", d, pt);
+        }
+    }
+
+    #[test]
+    fn exact_near_surface() {
+        let cache = SdfCache::new(2., 8, 1.);
+        let pt = Vec3::new(10.2, 0.3, 0.);
+        assert_eq!(sphere(&pt), cache.distance(&pt, &sphere));
+    }
+
+    #[test]
+    fn far_bricks_are_empty() {
+        let cache = SdfCache::new(2., 8, 1.);
+        assert_eq!(16., cache.distance(&Vec3::new(100., 100., 100.), &sphere));
+    }
+
+    #[test]
+    fn invalidate_rebuilds_nearby_bricks() {
+        let mut cache = SdfCache::new(2., 8, 1.);
+        let pt = Vec3::new(14., 0., 0.);
+        let before = cache.distance(&pt, &sphere);
+        cache.invalidate(&Aabb::from_point(&Vec3::new(14., 0., 0.)));
+        // after the sphere "moves", the cache picks up the new geometry.
+        let moved = |p: &Vec3| sphere(&p.clone().add_mut(-3., 0., 0.));
+        assert!(cache.distance(&pt, &moved) < before);
+    }
+}
@@ -0,0 +1,116 @@
+use std::f64::consts::PI;
+
+use crate::instance::Prototype;
+use crate::sdf::{curve_bounds, sdf_curve};
+use crate::threed::{Aabb, Vec3};
+use crate::utils::lerpf;
+
+// sepals are modeled this long in their own space, and scaled to size where they're placed.
+const SEPAL_LENGTH: f64 = 20.;
+
+// how much thinner a sepal is across its face than along it.
+const FLATNESS: f64 = 3.;
+
+// the green lobes cupping the underside of a bloom.
+#[derive(Clone, Debug)]
+pub struct Sepals {
+    pub count: usize,
+    // how long each sepal is.
+    pub size: f64,
+    // how far they curl back down toward the stem: 0 sticks straight out, 1 curls right over.
+    pub curvature: f64,
+}
+
+impl Sepals {
+    pub fn new(count: usize, size: f64, curvature: f64) -> Self {
+        Self { count, size, curvature }
+    }
+
+    // the angle around the bloom of each sepal, in radians.
+    pub fn angles(&self) -> Vec<f64> {
+        (0..self.count)
+            .map(|i| PI * 2. * i as f64 / self.count as f64)
+            .collect()
+    }
+
+    // how far each sepal leans out from the bloom's axis, in radians: past square, so they
+    // hang a little below the petals.
+    pub fn lean(&self) -> f64 {
+        lerpf(PI * 0.45, PI * 0.6, self.curvature)
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.size / SEPAL_LENGTH
+    }
+
+    pub fn prototype(&self) -> SepalPrototype {
+        SepalPrototype { curvature: self.curvature }
+    }
+}
+
+// a sepal in its own space: a narrow lobe running up +y from the origin, flat across z, and
+// curling back toward +z. variation makes it a little longer or shorter.
+pub struct SepalPrototype {
+    curvature: f64,
+}
+
+impl SepalPrototype {
+    pub fn spine(&self, variation: f64) -> impl Fn(f64) -> Vec3 {
+        let length = SEPAL_LENGTH * lerpf(0.85, 1.15, variation);
+        let curl = self.curvature * length * 0.6;
+        move |s| Vec3::bezier2(
+            &Vec3::zero(),
+            &Vec3::new(0., length * 0.6, 0.),
+            &Vec3::new(0., length - curl * 0.5, curl),
+            s,
+        )
+    }
+
+    fn thickness(s: f64) -> f64 {
+        // widest a third of the way along, narrowing to a point.
+        SEPAL_LENGTH * 0.12 * (1. - s) * lerpf(0.7, 1.2, (s * 3.).min(1.)) + 0.2
+    }
+}
+
+impl Prototype for SepalPrototype {
+    // a tube around the spine, squashed flat along z. squashing shrinks distances by up to
+    // FLATNESS, so they're scaled back down by the same to stay conservative.
+    fn distance(&self, pt: &Vec3, variation: f64) -> f64 {
+        let spine = self.spine(variation);
+        let flat = |s: f64| {
+            let p = spine(s);
+            Vec3::new(p.x, p.y, p.z * FLATNESS)
+        };
+        let pt = Vec3::new(pt.x, pt.y, pt.z * FLATNESS);
+        sdf_curve(&flat, &Self::thickness, &pt) / FLATNESS
+    }
+
+    fn bounds(&self) -> Aabb {
+        let thickness = Self::thickness(1. / 3.);
+        curve_bounds(&self.spine(0.), thickness)
+            .union(&curve_bounds(&self.spine(1.), thickness))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sepal::*;
+
+    #[test]
+    fn evenly_around() {
+        let angles = Sepals::new(5, 20., 0.5).angles();
+        assert_eq!(5, angles.len());
+        for pair in angles.windows(2) {
+            assert!((pair[1] - pair[0] - PI * 0.4).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn curls_back() {
+        let flat = Sepals::new(5, 20., 0.).prototype();
+        let curled = Sepals::new(5, 20., 1.).prototype();
+        assert_eq!(0., flat.spine(0.5)(1.).z);
+        assert!(curled.spine(0.5)(1.).z > SEPAL_LENGTH * 0.5);
+        assert!(curled.bounds().max.z >= curled.spine(1.)(1.).z);
+    }
+}
@@ -0,0 +1,73 @@
+use std::f64::consts::PI;
+
+use crate::threed::Vec3;
+
+// where dragged points are pulled to, so they line up exactly. only x and y snap; depth is
+// left alone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snap {
+    // the spacing of the grid points snap to, in the scene.
+    pub grid: Option<f64>,
+    // the step, in radians, that the direction from a point's anchor snaps to multiples of.
+    pub angle: Option<f64>,
+}
+
+impl Snap {
+    pub fn none() -> Self {
+        Self { grid: None, angle: None }
+    }
+
+    // where `pt` snaps to, given the point it's angled from, if any. angles win over the grid
+    // when both apply, since grid points are rarely at the right angle.
+    pub fn apply(&self, pt: &Vec3, anchor: Option<&Vec3>) -> Vec3 {
+        if let (Some(step), Some(anchor)) = (self.angle, anchor) {
+            let offset = Vec3::new(pt.x - anchor.x, pt.y - anchor.y, 0.);
+            let length = offset.mag();
+            if step > 0. && length > 0. {
+                let angle = (offset.y.atan2(offset.x) / step).round() * step;
+                return Vec3::new(anchor.x + length * angle.cos(), anchor.y + length * angle.sin(), pt.z);
+            }
+        }
+        match self.grid {
+            Some(spacing) if spacing > 0. => Vec3::new(
+                (pt.x / spacing).round() * spacing,
+                (pt.y / spacing).round() * spacing,
+                pt.z,
+            ),
+            _ => pt.clone(),
+        }
+    }
+}
+
+// a snapping angle in degrees, as it's given from js, with 0 meaning none.
+pub fn angle_step(degrees: f64) -> Option<f64> {
+    if degrees > 0. { Some(degrees * PI / 180.) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snap::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!(a.dist(b) < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn to_grid() {
+        let snap = Snap { grid: Some(10.), angle: None };
+        assert_near(&Vec3::new(20., -10., 3.), &snap.apply(&Vec3::new(17., -12., 3.), None));
+        assert_near(&Vec3::new(17., -12., 3.), &Snap::none().apply(&Vec3::new(17., -12., 3.), None));
+    }
+
+    #[test]
+    fn to_angles() {
+        let snap = Snap { grid: Some(10.), angle: angle_step(45.) };
+        let anchor = Vec3::new(100., 100., 0.);
+        // about 40 degrees off the anchor snaps to 45, keeping its distance.
+        let snapped = snap.apply(&Vec3::new(100. + 40f64.to_radians().cos() * 50., 100. + 40f64.to_radians().sin() * 50., 0.), Some(&anchor));
+        assert_near(&Vec3::new(100. + 50. / 2f64.sqrt(), 100. + 50. / 2f64.sqrt(), 0.), &snapped);
+        // without an anchor, it falls back to the grid.
+        assert_near(&Vec3::new(10., 0., 0.), &snap.apply(&Vec3::new(12., 1., 0.), None));
+        assert_eq!(None, angle_step(0.));
+    }
+}
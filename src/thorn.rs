@@ -0,0 +1,124 @@
+use std::f64::consts::PI;
+
+use crate::instance::Prototype;
+use crate::sdf::{curve_bounds, sdf_curve};
+use crate::threed::{Aabb, Vec3};
+use crate::utils::lerpf;
+
+// thorns are modeled this long in their own space, and scaled to size where they're placed.
+const THORN_LENGTH: f64 = 10.;
+
+// how far through the stem a thorn's base sits, as a fraction of the stem's thickness, so it
+// blends in rather than perching on the surface.
+pub const THORN_DEPTH: f64 = 0.4;
+
+// thorns scattered along the stem, all hooking back toward its base.
+#[derive(Clone, Debug)]
+pub struct Thorns {
+    pub count: usize,
+    // the part of the stem they grow along.
+    pub start: f64,
+    pub end: f64,
+    // how long each thorn is.
+    pub size: f64,
+    // how far the tip hooks over: 0 is straight, 1 bends it over by about its length.
+    pub curvature: f64,
+}
+
+impl Thorns {
+    pub fn new(count: usize, size: f64, curvature: f64) -> Self {
+        Self {
+            count,
+            start: 0.08,
+            end: 0.85,
+            size,
+            curvature,
+        }
+    }
+
+    // where each thorn goes, as (parameter along the stem, angle around it in radians,
+    // variation). they're spaced evenly with a little jitter, and scattered around the stem
+    // without ever lining up.
+    pub fn placements(&self) -> Vec<(f64, f64, f64)> {
+        (0..self.count)
+            .map(|i| {
+                let variation = (i as f64 * 0.618_033_988_75).fract();
+                let t = (i as f64 + lerpf(0.3, 0.7, variation)) / self.count as f64;
+                let angle = (i as f64 * 2.4 + variation) % (PI * 2.);
+                (lerpf(self.start, self.end, t), angle, variation)
+            })
+            .collect()
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.size / THORN_LENGTH
+    }
+
+    pub fn prototype(&self) -> ThornPrototype {
+        ThornPrototype { curvature: self.curvature }
+    }
+}
+
+// a thorn in its own space: a cone rising up +y from a wide base at the origin, its tip hooking
+// over toward -x. variation hooks some thorns more than others.
+pub struct ThornPrototype {
+    curvature: f64,
+}
+
+impl ThornPrototype {
+    pub fn spine(&self, variation: f64) -> impl Fn(f64) -> Vec3 {
+        let hook = self.curvature * lerpf(0.7, 1.3, variation) * THORN_LENGTH;
+        move |s| Vec3::bezier2(
+            &Vec3::zero(),
+            &Vec3::new(0., THORN_LENGTH * 0.7, 0.),
+            &Vec3::new(-hook, THORN_LENGTH * (1. - hook / THORN_LENGTH * 0.3), 0.),
+            s,
+        )
+    }
+
+    fn thickness(s: f64) -> f64 {
+        lerpf(THORN_LENGTH * 0.3, 0.15, s)
+    }
+}
+
+impl Prototype for ThornPrototype {
+    fn distance(&self, pt: &Vec3, variation: f64) -> f64 {
+        sdf_curve(&self.spine(variation), &Self::thickness, pt)
+    }
+
+    fn bounds(&self) -> Aabb {
+        // spines move linearly with the hook, so the two extremes cover everything between.
+        curve_bounds(&self.spine(0.), Self::thickness(0.))
+            .union(&curve_bounds(&self.spine(1.), Self::thickness(0.)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::thorn::*;
+
+    #[test]
+    fn placements_stay_on_the_stretch() {
+        let thorns = Thorns::new(12, 8., 0.5);
+        let placements = thorns.placements();
+        assert_eq!(12, placements.len());
+        let mut last = thorns.start;
+        for (s, angle, variation) in placements {
+            assert!(s >= last && s <= thorns.end, "{} out of order", s);
+            assert!((0. ..PI * 2.).contains(&angle));
+            assert!((0. ..1.).contains(&variation));
+            last = s;
+        }
+    }
+
+    #[test]
+    fn hooks_toward_minus_x() {
+        let straight = Thorns::new(1, 10., 0.).prototype();
+        let hooked = Thorns::new(1, 10., 1.).prototype();
+        assert_eq!(0., straight.spine(0.5)(1.).x);
+        assert!(hooked.spine(0.5)(1.).x < -THORN_LENGTH * 0.5);
+        assert!(hooked.spine(1.)(1.).x < hooked.spine(0.)(1.).x);
+        let bounds = hooked.bounds();
+        assert!(bounds.min.x <= hooked.spine(1.)(1.).x && bounds.max.y >= THORN_LENGTH * 0.7);
+    }
+}
@@ -1,9 +1,16 @@
 use std::ops;
 use std::fmt::{Display, Formatter};
-use js_sys::Math::sqrt;
 use wasm_bindgen::__rt::core::ops::{BitXor, Div};
 
-#[derive(Clone, Debug)]
+mod curve_frame;
+mod matrix;
+mod quat;
+
+pub use curve_frame::{perpendicular, tangent, CurveFrames};
+pub use matrix::{Mat3, Mat4};
+pub use quat::Quat;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -100,7 +107,7 @@ impl Vec3 {
     }
 
     pub fn mag(&self) -> f64 {
-        sqrt(self.mag2())
+        self.mag2().sqrt()
     }
 
     pub fn dist2(&self, other: &Vec3) -> f64 {
@@ -111,7 +118,7 @@ impl Vec3 {
     }
 
     pub fn dist(&self, other: &Vec3) -> f64 {
-        sqrt(self.dist2(other))
+        self.dist2(other).sqrt()
     }
 
     pub fn unit(&self) -> Vec3 {
@@ -119,7 +126,7 @@ impl Vec3 {
         if m == 1. || m == 0. {
             self.clone()
         } else {
-            self / sqrt(m)
+            self / m.sqrt()
         }
     }
 
@@ -306,6 +313,18 @@ impl Basis {
             global_vec.dot(&self.axes[2]) / self.axes[2].mag2()
         )
     }
+
+    pub fn rotated(&self, rotation: &Quat) -> Basis {
+        Basis::new(
+            rotation.rotate(&self.axes[0]),
+            rotation.rotate(&self.axes[1]),
+            rotation.rotate(&self.axes[2]),
+        )
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        Mat3::from_basis(self)
+    }
 }
 
 #[derive(Clone)]
@@ -337,6 +356,33 @@ impl Frame {
     pub fn unproject(&self, global_point: &Vec3) -> Vec3 {
         self.basis.unproject(&(global_point - &self.origin))
     }
+
+    pub fn origin(&self) -> &Vec3 {
+        &self.origin
+    }
+
+    pub fn basis(&self) -> &Basis {
+        &self.basis
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_frame(self)
+    }
+
+    // `local`, which is given relative to this frame, relative to the world instead.
+    pub fn compose(&self, local: &Frame) -> Frame {
+        (&self.to_mat4() * &local.to_mat4()).to_frame()
+    }
+
+    // the world, relative to this frame; none if the basis is degenerate.
+    pub fn inverse(&self) -> Option<Frame> {
+        self.to_mat4().inverse().map(|m| m.to_frame())
+    }
+
+    // spins the frame in place around its origin.
+    pub fn rotated(&self, rotation: &Quat) -> Frame {
+        Frame::from_basis(self.origin.clone(), self.basis.rotated(rotation))
+    }
 }
 
 #[derive(Clone)]
@@ -361,6 +407,57 @@ impl Display for Ray {
     }
 }
 
+// axis-aligned bounding box
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_point(pt: &Vec3) -> Self {
+        Self::new(pt.clone(), pt.clone())
+    }
+
+    pub fn include(mut self, pt: &Vec3) -> Self {
+        self.min = Vec3::new(self.min.x.min(pt.x), self.min.y.min(pt.y), self.min.z.min(pt.z));
+        self.max = Vec3::new(self.max.x.max(pt.x), self.max.y.max(pt.y), self.max.z.max(pt.z));
+        self
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        self.clone().include(&other.min).include(&other.max)
+    }
+
+    pub fn expand(&self, margin: f64) -> Aabb {
+        Aabb::new(
+            self.min.clone().add_mut(-margin, -margin, -margin),
+            self.max.clone().add_mut(margin, margin, margin),
+        )
+    }
+
+    // the box around all eight corners, after moving each of them by `f`.
+    pub fn transformed<F: Fn(&Vec3) -> Vec3>(&self, f: F) -> Aabb {
+        let mut bounds: Option<Aabb> = None;
+        for &x in &[self.min.x, self.max.x] {
+            for &y in &[self.min.y, self.max.y] {
+                for &z in &[self.min.z, self.max.z] {
+                    let corner = f(&Vec3::new(x, y, z));
+                    bounds = Some(match bounds {
+                        None => Aabb::from_point(&corner),
+                        Some(b) => b.include(&corner),
+                    });
+                }
+            }
+        }
+        bounds.unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::threed::*;
@@ -369,4 +466,37 @@ mod tests {
     fn subtraction() {
         assert_eq!("<1, 2, 3>", (&Vec3::new(4., 5., 6.) - &Vec3::new(3., 3., 3.)).to_string());
     }
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!(a.dist(b) < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn frame_transforms() {
+        let parent = Frame::new(
+            Vec3::new(5., 0., 0.),
+            Vec3::new(0., 2., 0.),
+            Vec3::new(-2., 0., 0.),
+            Vec3::new(0., 0., 2.));
+        let child = Frame::new(Vec3::new(1., 0., 0.), Vec3::right(), Vec3::up(), Vec3::forward())
+            .rotated(&Quat::from_axis_angle(&Vec3::forward(), 0.3));
+        let local = Vec3::new(0.5, -1., 2.);
+
+        let composed = parent.compose(&child);
+        assert_near(&parent.project(&child.project(&local)), &composed.project(&local));
+        assert_near(&composed.project(&local), &composed.to_mat4().transform_point(&local));
+        assert_near(&local, &composed.unproject(&composed.project(&local)));
+        let inverse = parent.inverse().unwrap();
+        assert_near(&parent.unproject(&local), &inverse.project(&local));
+        assert_near(&Vec3::new(5., 0., 0.), parent.origin());
+    }
+
+    #[test]
+    fn aabb_include() {
+        let aabb = Aabb::from_point(&Vec3::new(1., 2., 3.))
+            .include(&Vec3::new(-1., 5., 0.))
+            .expand(1.);
+        assert_eq!("<-2, 1, -1>", aabb.min.to_string());
+        assert_eq!("<2, 6, 4>", aabb.max.to_string());
+    }
 }
@@ -0,0 +1,208 @@
+use crate::threed::{Frame, Vec3};
+
+// step used to differentiate curves numerically.
+const H: f64 = 0.001;
+
+// an orthonormal frame riding along a curve.
+#[derive(Clone, Debug)]
+pub struct CurveFrame {
+    pub position: Vec3,
+    pub tangent: Vec3,
+    pub normal: Vec3,
+    pub binormal: Vec3,
+}
+
+impl CurveFrame {
+    // a `Frame` at the curve with x along the binormal, y along the tangent and z along the
+    // normal, each scaled by `scale`.
+    pub fn to_frame(&self, scale: f64) -> Frame {
+        Frame::new(
+            self.position.clone(),
+            &self.binormal * scale,
+            &self.tangent * scale,
+            &self.normal * scale,
+        )
+    }
+}
+
+pub fn tangent<C: Fn(f64) -> Vec3>(curve: &C, s: f64) -> Vec3 {
+    (&curve(s + H) - &curve(s - H)).unit()
+}
+
+// any unit vector perpendicular to `v`, preferring `hint` if it isn't parallel to `v`.
+pub fn perpendicular(v: &Vec3, hint: &Vec3) -> Vec3 {
+    let v = v.unit();
+    for candidate in &[hint.clone(), Vec3::right(), Vec3::up()] {
+        let p = candidate - &(&v * v.dot(candidate));
+        if !p.is_zero(0.0001) {
+            return p.unit();
+        }
+    }
+    unreachable!("right and up can't both be parallel to a vector.")
+}
+
+// frames sampled evenly along a curve, from `from` to `to` inclusive.
+#[derive(Clone, Debug)]
+pub struct CurveFrames {
+    from: f64,
+    to: f64,
+    frames: Vec<CurveFrame>,
+}
+
+impl CurveFrames {
+    // the classic frenet frames: the normal points toward the center of curvature. they're
+    // only defined where the curve actually bends, and spin suddenly through inflections, so
+    // straight stretches reuse the last good normal (or `hint`, at the start).
+    pub fn frenet<C: Fn(f64) -> Vec3>(curve: &C, from: f64, to: f64, samples: usize, hint: &Vec3) -> Self {
+        let mut frames: Vec<CurveFrame> = Vec::with_capacity(samples + 1);
+        for i in 0..=samples {
+            let s = Self::param(from, to, samples, i);
+            let tangent = tangent(curve, s);
+            let second = &(&curve(s + H) + &curve(s - H)) - &(&curve(s) * 2.);
+            let bending = &second - &(&tangent * tangent.dot(&second));
+            let normal = if bending.mag2() > 1e-18 {
+                bending.unit()
+            } else {
+                perpendicular(&tangent, frames.last().map(|f| &f.normal).unwrap_or(hint))
+            };
+            frames.push(CurveFrame {
+                position: curve(s),
+                binormal: tangent.cross(&normal).unit(),
+                tangent,
+                normal,
+            });
+        }
+        Self { from, to, frames }
+    }
+
+    // rotation-minimizing frames by double reflection (wang et al. 2008): each frame is the
+    // last one reflected onto the next sample, so the normal only ever turns as much as the
+    // curve forces it to, and never flips. `normal` is the normal to start with; it's made
+    // perpendicular to the curve if it isn't already.
+    pub fn rotation_minimizing<C: Fn(f64) -> Vec3>(curve: &C, from: f64, to: f64, samples: usize, normal: &Vec3) -> Self {
+        let mut frames: Vec<CurveFrame> = Vec::with_capacity(samples + 1);
+
+        let tangent0 = tangent(curve, from);
+        let normal0 = perpendicular(&tangent0, normal);
+        frames.push(CurveFrame {
+            position: curve(from),
+            binormal: tangent0.cross(&normal0).unit(),
+            tangent: tangent0,
+            normal: normal0,
+        });
+
+        for i in 1..=samples {
+            let s = Self::param(from, to, samples, i);
+            let prev = &frames[i - 1];
+            let position = curve(s);
+            let tangent = tangent(curve, s);
+
+            let reflect = |v: &Vec3, axis: &Vec3| -> Vec3 {
+                let c = axis.mag2();
+                if c < 1e-18 { v.clone() } else { v - &(axis * (2. / c * axis.dot(v))) }
+            };
+            let v1 = &position - &prev.position;
+            let normal_l = reflect(&prev.normal, &v1);
+            let tangent_l = reflect(&prev.tangent, &v1);
+            let v2 = &tangent - &tangent_l;
+            let normal = perpendicular(&tangent, &reflect(&normal_l, &v2));
+
+            frames.push(CurveFrame {
+                position,
+                binormal: tangent.cross(&normal).unit(),
+                tangent,
+                normal,
+            });
+        }
+        Self { from, to, frames }
+    }
+
+    pub fn frames(&self) -> &[CurveFrame] {
+        &self.frames
+    }
+
+    // the frame at any `s` between `from` and `to`, blended from the nearest samples.
+    pub fn at(&self, s: f64) -> CurveFrame {
+        let samples = self.frames.len() - 1;
+        if samples == 0 || self.to == self.from {
+            return self.frames[0].clone();
+        }
+        let x = ((s - self.from) / (self.to - self.from) * samples as f64).clamp(0., samples as f64);
+        let i = (x.floor() as usize).min(samples - 1);
+        let t = x - i as f64;
+        let (a, b) = (&self.frames[i], &self.frames[i + 1]);
+
+        let tangent = Vec3::lerp(&a.tangent, &b.tangent, t).unit();
+        let normal = perpendicular(&tangent, &Vec3::lerp(&a.normal, &b.normal, t));
+        CurveFrame {
+            position: Vec3::lerp(&a.position, &b.position, t),
+            binormal: tangent.cross(&normal).unit(),
+            tangent,
+            normal,
+        }
+    }
+
+    fn param(from: f64, to: f64, samples: usize, i: usize) -> f64 {
+        if samples == 0 { from } else { from + (to - from) * i as f64 / samples as f64 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::threed::curve_frame::*;
+
+    fn helix(s: f64) -> Vec3 {
+        let a = s * std::f64::consts::PI * 4.;
+        Vec3::new(10. * a.cos(), 10. * a.sin(), 8. * s)
+    }
+
+    fn assert_orthonormal(frame: &CurveFrame) {
+        for (a, b) in &[
+            (&frame.tangent, &frame.normal),
+            (&frame.normal, &frame.binormal),
+            (&frame.binormal, &frame.tangent),
+        ] {
+            assert!(a.dot(b).abs() < 1e-6, "not orthogonal at {}", frame.position);
+            assert!((a.mag() - 1.).abs() < 1e-6, "not unit at {}", frame.position);
+        }
+    }
+
+    #[test]
+    fn straight_line_keeps_its_normal() {
+        let line = |s: f64| Vec3::new(0., -100. * s, 0.);
+        let frames = CurveFrames::rotation_minimizing(&line, 0., 1., 10, &Vec3::new(0., 0., -1.));
+        for frame in frames.frames() {
+            assert!(frame.normal.dist(&Vec3::new(0., 0., -1.)) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rotation_minimizing_helix() {
+        let frames = CurveFrames::rotation_minimizing(&helix, 0., 1., 64, &Vec3::forward());
+        let mut prev: Option<&CurveFrame> = None;
+        for (i, frame) in frames.frames().iter().enumerate() {
+            assert_orthonormal(frame);
+            assert!(frame.tangent.dist(&tangent(&helix, i as f64 / 64.)) < 1e-9);
+            if let Some(prev) = prev {
+                // no sudden flips between neighboring samples.
+                assert!(prev.normal.dot(&frame.normal) > 0.9);
+            }
+            prev = Some(frame);
+        }
+        assert_orthonormal(&frames.at(0.37));
+    }
+
+    #[test]
+    fn frenet_normal_points_inward() {
+        let circle = |s: f64| {
+            let a = s * std::f64::consts::PI * 2.;
+            Vec3::new(5. * a.cos(), 5. * a.sin(), 0.)
+        };
+        let frames = CurveFrames::frenet(&circle, 0., 1., 8, &Vec3::forward());
+        for frame in frames.frames() {
+            assert_orthonormal(frame);
+            let inward = (&Vec3::zero() - &frame.position).unit();
+            assert!(frame.normal.dist(&inward) < 1e-3);
+        }
+    }
+}
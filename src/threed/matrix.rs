@@ -0,0 +1,297 @@
+use std::ops;
+
+use crate::threed::{Basis, Frame, Quat, Vec3};
+
+// a linear transform (rotation, scale, shear), as rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mat3 {
+    pub rows: [[f64; 3]; 3],
+}
+
+impl Mat3 {
+    pub fn new(rows: [[f64; 3]; 3]) -> Self {
+        Self { rows }
+    }
+
+    pub fn identity() -> Self {
+        Self::from_basis(&Basis::identity())
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        Self::new([[x, 0., 0.], [0., y, 0.], [0., 0., z]])
+    }
+
+    // the matrix whose columns are the basis axes, so it takes local vectors to global ones
+    // like `Basis::project`.
+    pub fn from_basis(basis: &Basis) -> Self {
+        let [x, y, z] = [&basis.axes[0], &basis.axes[1], &basis.axes[2]];
+        Self::new([[x.x, y.x, z.x], [x.y, y.y, z.y], [x.z, y.z, z.z]])
+    }
+
+    pub fn to_basis(&self) -> Basis {
+        Basis::new(self.column(0), self.column(1), self.column(2))
+    }
+
+    pub fn column(&self, i: usize) -> Vec3 {
+        Vec3::new(self.rows[0][i], self.rows[1][i], self.rows[2][i])
+    }
+
+    pub fn transform(&self, v: &Vec3) -> Vec3 {
+        let r = &self.rows;
+        Vec3::new(
+            r[0][0] * v.x + r[0][1] * v.y + r[0][2] * v.z,
+            r[1][0] * v.x + r[1][1] * v.y + r[1][2] * v.z,
+            r[2][0] * v.x + r[2][1] * v.y + r[2][2] * v.z,
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.rows[j][i];
+            }
+        }
+        Self::new(rows)
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let r = &self.rows;
+        r[0][0] * (r[1][1] * r[2][2] - r[1][2] * r[2][1])
+            - r[0][1] * (r[1][0] * r[2][2] - r[1][2] * r[2][0])
+            + r[0][2] * (r[1][0] * r[2][1] - r[1][1] * r[2][0])
+    }
+
+    // none if the transform squashes space flat, or isn't finite.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if !det.is_finite() || det.abs() < 1e-12 {
+            return None;
+        }
+        // the inverse is the transposed cofactors (the cross products of pairs of columns,
+        // as rows) over the determinant.
+        let (x, y, z) = (self.column(0), self.column(1), self.column(2));
+        let rows = [y.cross(&z), z.cross(&x), x.cross(&y)];
+        Some(Self::new([
+            [rows[0].x / det, rows[0].y / det, rows[0].z / det],
+            [rows[1].x / det, rows[1].y / det, rows[1].z / det],
+            [rows[2].x / det, rows[2].y / det, rows[2].z / det],
+        ]))
+    }
+
+    // the rotation this matrix represents; only meaningful for orthonormal matrices.
+    pub fn to_quat(&self) -> Quat {
+        let r = &self.rows;
+        let trace = r[0][0] + r[1][1] + r[2][2];
+        // divide by the largest of the four candidates to stay numerically stable.
+        let q = if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Quat::new(s / 4., (r[2][1] - r[1][2]) / s, (r[0][2] - r[2][0]) / s, (r[1][0] - r[0][1]) / s)
+        } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+            let s = (1. + r[0][0] - r[1][1] - r[2][2]).sqrt() * 2.;
+            Quat::new((r[2][1] - r[1][2]) / s, s / 4., (r[0][1] + r[1][0]) / s, (r[0][2] + r[2][0]) / s)
+        } else if r[1][1] > r[2][2] {
+            let s = (1. + r[1][1] - r[0][0] - r[2][2]).sqrt() * 2.;
+            Quat::new((r[0][2] - r[2][0]) / s, (r[0][1] + r[1][0]) / s, s / 4., (r[1][2] + r[2][1]) / s)
+        } else {
+            let s = (1. + r[2][2] - r[0][0] - r[1][1]).sqrt() * 2.;
+            Quat::new((r[1][0] - r[0][1]) / s, (r[0][2] + r[2][0]) / s, (r[1][2] + r[2][1]) / s, s / 4.)
+        };
+        q.normalized()
+    }
+}
+
+// composes transforms: (a * b) applies b first, then a.
+impl ops::Mul<&Mat3> for &Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: &Mat3) -> Self::Output {
+        let mut rows = [[0.; 3]; 3];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..3).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Mat3::new(rows)
+    }
+}
+
+// a homogeneous transform, as rows. affine ones (the last row 0, 0, 0, 1) move points and
+// directions differently: directions ignore translation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn identity() -> Self {
+        Self::from_mat3(&Mat3::identity(), &Vec3::zero())
+    }
+
+    pub fn translation(v: &Vec3) -> Self {
+        Self::from_mat3(&Mat3::identity(), v)
+    }
+
+    pub fn rotation(q: &Quat) -> Self {
+        Self::from_mat3(&q.to_mat3(), &Vec3::zero())
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        Self::from_mat3(&Mat3::scale(x, y, z), &Vec3::zero())
+    }
+
+    // the linear part, followed by a translation.
+    pub fn from_mat3(linear: &Mat3, translation: &Vec3) -> Self {
+        let r = &linear.rows;
+        Self::new([
+            [r[0][0], r[0][1], r[0][2], translation.x],
+            [r[1][0], r[1][1], r[1][2], translation.y],
+            [r[2][0], r[2][1], r[2][2], translation.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    // takes local points to global ones like `Frame::project`.
+    pub fn from_frame(frame: &Frame) -> Self {
+        Self::from_mat3(&Mat3::from_basis(&frame.basis), &frame.origin)
+    }
+
+    // the frame this (affine) transform maps the identity frame to.
+    pub fn to_frame(&self) -> Frame {
+        Frame::from_basis(self.translation_part(), self.linear().to_basis())
+    }
+
+    pub fn linear(&self) -> Mat3 {
+        let r = &self.rows;
+        Mat3::new([
+            [r[0][0], r[0][1], r[0][2]],
+            [r[1][0], r[1][1], r[1][2]],
+            [r[2][0], r[2][1], r[2][2]],
+        ])
+    }
+
+    pub fn translation_part(&self) -> Vec3 {
+        Vec3::new(self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let v = self.apply(p, 1.);
+        let w = self.rows[3][0] * p.x + self.rows[3][1] * p.y + self.rows[3][2] * p.z + self.rows[3][3];
+        if w == 1. || w == 0. { v } else { &v / w }
+    }
+
+    pub fn transform_direction(&self, d: &Vec3) -> Vec3 {
+        self.apply(d, 0.)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.rows[j][i];
+            }
+        }
+        Self::new(rows)
+    }
+
+    // gauss-jordan elimination with partial pivoting; none if the matrix is singular or has
+    // non-finite entries.
+    pub fn inverse(&self) -> Option<Self> {
+        if !self.rows.iter().flatten().all(|v| v.is_finite()) {
+            return None;
+        }
+        let mut a = self.rows;
+        let mut inv = Self::identity().rows;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let p = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= p;
+                inv[col][j] /= p;
+            }
+            for i in 0..4 {
+                if i == col {
+                    continue;
+                }
+                let f = a[i][col];
+                for j in 0..4 {
+                    a[i][j] -= f * a[col][j];
+                    inv[i][j] -= f * inv[col][j];
+                }
+            }
+        }
+        Some(Self::new(inv))
+    }
+
+    fn apply(&self, v: &Vec3, w: f64) -> Vec3 {
+        let r = &self.rows;
+        Vec3::new(
+            r[0][0] * v.x + r[0][1] * v.y + r[0][2] * v.z + r[0][3] * w,
+            r[1][0] * v.x + r[1][1] * v.y + r[1][2] * v.z + r[1][3] * w,
+            r[2][0] * v.x + r[2][1] * v.y + r[2][2] * v.z + r[2][3] * w,
+        )
+    }
+}
+
+// composes transforms: (a * b) applies b first, then a.
+impl ops::Mul<&Mat4> for &Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: &Mat4) -> Self::Output {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..4).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Mat4::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::threed::matrix::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!(a.dist(b) < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn mat3_inverse() {
+        let m = Mat3::new([[2., 0., 1.], [1., 3., 0.], [0., 1., 4.]]);
+        let v = Vec3::new(1., 2., 3.);
+        assert_near(&v, &m.inverse().unwrap().transform(&m.transform(&v)));
+        assert!(Mat3::scale(1., 0., 1.).inverse().is_none());
+        assert_eq!(m, m.transpose().transpose());
+    }
+
+    #[test]
+    fn mat4_points_and_directions() {
+        let m = &Mat4::translation(&Vec3::new(10., 0., 0.))
+            * &Mat4::rotation(&Quat::from_axis_angle(&Vec3::forward(), std::f64::consts::FRAC_PI_2));
+        assert_near(&Vec3::new(10., 1., 0.), &m.transform_point(&Vec3::right()));
+        assert_near(&Vec3::up(), &m.transform_direction(&Vec3::right()));
+        let inverse = m.inverse().unwrap();
+        assert_near(&Vec3::right(), &inverse.transform_point(&Vec3::new(10., 1., 0.)));
+        assert_eq!(Mat4::identity(), Mat4::identity().transpose());
+    }
+
+    #[test]
+    fn mat4_inverse_of_nan() {
+        let mut m = Mat4::identity();
+        m.rows[1][1] = f64::NAN;
+        assert!(m.inverse().is_none());
+    }
+}
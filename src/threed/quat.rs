@@ -0,0 +1,186 @@
+use std::fmt::{Display, Formatter};
+use std::ops;
+
+use crate::threed::{Basis, Mat3, Vec3, curve_frame::perpendicular};
+
+// a rotation, as a unit quaternion w + xi + yj + zk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quat {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1., 0., 0., 0.)
+    }
+
+    // turns `angle` radians around `axis`, counterclockwise looking down the axis toward the
+    // origin.
+    pub fn from_axis_angle(axis: &Vec3, angle: f64) -> Self {
+        let axis = axis.unit();
+        let (sin, cos) = (angle / 2.).sin_cos();
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    // the smallest rotation taking the direction of `from` to the direction of `to`.
+    pub fn from_to(from: &Vec3, to: &Vec3) -> Self {
+        let (from, to) = (from.unit(), to.unit());
+        let d = from.dot(&to);
+        if d < -1. + 1e-12 {
+            // opposite directions: any half turn around a perpendicular axis will do.
+            return Self::from_axis_angle(&perpendicular(&from, &Vec3::right()), std::f64::consts::PI);
+        }
+        let c = from.cross(&to);
+        Self::new(1. + d, c.x, c.y, c.z).normalized()
+    }
+
+    // the rotation that takes the identity basis's axes to the (orthonormal) axes of `basis`.
+    pub fn from_basis(basis: &Basis) -> Self {
+        Mat3::from_basis(basis).to_quat()
+    }
+
+    pub fn axis_angle(&self) -> (Vec3, f64) {
+        let q = self.normalized();
+        let sin = (1. - q.w * q.w).max(0.).sqrt();
+        if sin < 1e-12 {
+            return (Vec3::right(), 0.);
+        }
+        (Vec3::new(q.x / sin, q.y / sin, q.z / sin), 2. * q.w.clamp(-1., 1.).acos())
+    }
+
+    pub fn dot(&self, other: &Quat) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalized(&self) -> Self {
+        let m = self.dot(self).sqrt();
+        Self::new(self.w / m, self.x / m, self.y / m, self.z / m)
+    }
+
+    // the opposite rotation.
+    pub fn inverse(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    // `self` followed by `other`.
+    pub fn then(&self, other: &Quat) -> Self {
+        other * self
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        // v + 2w(u × v) + 2u × (u × v), for the vector part u.
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = &u.cross(v) * 2.;
+        &(v + &(&t * self.w)) + &u.cross(&t)
+    }
+
+    // spherical interpolation, at a constant angular speed along the shorter arc.
+    pub fn slerp(&self, other: &Quat, t: f64) -> Self {
+        let mut d = self.dot(other);
+        // q and -q are the same rotation; pick whichever is closer.
+        let other = if d < 0. {
+            d = -d;
+            Self::new(-other.w, -other.x, -other.y, -other.z)
+        } else {
+            other.clone()
+        };
+
+        let (a, b) = if d > 0.9995 {
+            // nearly parallel; lerp is accurate and avoids dividing by a tiny sine.
+            (1. - t, t)
+        } else {
+            let theta = d.acos();
+            let sin = theta.sin();
+            (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Self::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        ).normalized()
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        let Quat { w, x, y, z } = self.normalized();
+        Mat3::new([
+            [1. - 2. * (y * y + z * z), 2. * (x * y - w * z), 2. * (x * z + w * y)],
+            [2. * (x * y + w * z), 1. - 2. * (x * x + z * z), 2. * (y * z - w * x)],
+            [2. * (x * z - w * y), 2. * (y * z + w * x), 1. - 2. * (x * x + y * y)],
+        ])
+    }
+}
+
+// composes rotations: (a * b) rotates by b first, then a.
+impl ops::Mul<&Quat> for &Quat {
+    type Output = Quat;
+
+    fn mul(self, rhs: &Quat) -> Self::Output {
+        let (a, b) = (self, rhs);
+        Quat::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+    }
+}
+
+impl Display for Quat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Quat({}, {}, {}, {})", self.w, self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::threed::quat::*;
+
+    fn assert_near(a: &Vec3, b: &Vec3) {
+        assert!(a.dist(b) < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn rotation() {
+        let quarter = Quat::from_axis_angle(&Vec3::forward(), std::f64::consts::FRAC_PI_2);
+        assert_near(&Vec3::up(), &quarter.rotate(&Vec3::right()));
+        assert_near(&Vec3::right(), &quarter.inverse().rotate(&Vec3::up()));
+
+        let (axis, angle) = quarter.then(&quarter).axis_angle();
+        assert_near(&Vec3::forward(), &axis);
+        assert!((angle - std::f64::consts::PI).abs() < 1e-9);
+
+        let from = Vec3::new(1., 2., 3.);
+        let to = Vec3::new(-3., 0., 1.);
+        assert_near(&to.unit(), &Quat::from_to(&from, &to).rotate(&from).unit());
+        assert_near(&from.unit().flipped(), &Quat::from_to(&from, &from.flipped()).rotate(&from.unit()));
+    }
+
+    #[test]
+    fn slerp() {
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(&Vec3::up(), 2.);
+        let (axis, angle) = a.slerp(&b, 0.25).axis_angle();
+        assert_near(&Vec3::up(), &axis);
+        assert!((angle - 0.5).abs() < 1e-9);
+        assert_eq!(a, a.slerp(&b, 0.));
+    }
+
+    #[test]
+    fn matrix_roundtrip() {
+        let q = Quat::from_axis_angle(&Vec3::new(1., -2., 0.5), 2.5);
+        let v = Vec3::new(3., 4., -5.);
+        assert_near(&q.rotate(&v), &q.to_mat3().transform(&v));
+        let back = q.to_mat3().to_quat();
+        assert!(back.dot(&q).abs() > 1. - 1e-9);
+        let basis = Basis::identity().rotated(&q);
+        assert!(Quat::from_basis(&basis).dot(&q).abs() > 1. - 1e-9);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use crate::viewport::Rect;
+
+// splits the canvas into square tiles, and keeps a queue of the ones that need re-rendering.
+pub struct TileQueue {
+    width: f64,
+    height: f64,
+    tile_size: f64,
+    columns: usize,
+    rows: usize,
+    queued: Vec<bool>,
+    queue: VecDeque<usize>,
+}
+
+impl TileQueue {
+    pub fn new(width: f64, height: f64, tile_size: usize) -> Self {
+        let tile_size = tile_size as f64;
+        let columns = (width / tile_size).ceil() as usize;
+        let rows = (height / tile_size).ceil() as usize;
+        Self {
+            width,
+            height,
+            tile_size,
+            columns,
+            rows,
+            queued: vec![false; columns * rows],
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn tile_rect(&self, index: usize) -> Rect {
+        let column = index % self.columns;
+        let row = index / self.columns;
+        Rect::new(
+            column as f64 * self.tile_size,
+            row as f64 * self.tile_size,
+            self.tile_size,
+            self.tile_size,
+        ).intersection(&Rect::new(0., 0., self.width, self.height))
+    }
+
+    pub fn mark(&mut self, rect: &Rect) {
+        let rect = rect.intersection(&Rect::new(0., 0., self.width, self.height));
+        if rect.is_empty() {
+            return;
+        }
+        let c0 = (rect.x / self.tile_size).floor() as usize;
+        let r0 = (rect.y / self.tile_size).floor() as usize;
+        let c1 = ((rect.right() / self.tile_size).ceil() as usize).min(self.columns);
+        let r1 = ((rect.bottom() / self.tile_size).ceil() as usize).min(self.rows);
+        for row in r0..r1 {
+            for column in c0..c1 {
+                self.enqueue(row * self.columns + column);
+            }
+        }
+    }
+
+    pub fn mark_all(&mut self) {
+        self.mark_where(|_| true);
+    }
+
+    pub fn mark_where<F: Fn(&Rect) -> bool>(&mut self, predicate: F) {
+        for i in 0..self.queued.len() {
+            if predicate(&self.tile_rect(i)) {
+                self.enqueue(i);
+            }
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Rect> {
+        let index = self.queue.pop_front()?;
+        self.queued[index] = false;
+        Some(self.tile_rect(index))
+    }
+
+    fn enqueue(&mut self, index: usize) {
+        if !self.queued[index] {
+            self.queued[index] = true;
+            self.queue.push_back(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tiles::*;
+
+    #[test]
+    fn mark_overlapping() {
+        let mut tiles = TileQueue::new(100., 100., 32);
+        tiles.mark(&Rect::new(30., 10., 4., 4.));
+        // a rect straddling two tiles queues both, and re-marking doesn't duplicate them.
+        tiles.mark(&Rect::new(31., 11., 2., 2.));
+        assert_eq!(Some(Rect::new(0., 0., 32., 32.)), tiles.pop());
+        assert_eq!(Some(Rect::new(32., 0., 32., 32.)), tiles.pop());
+        assert_eq!(None, tiles.pop());
+    }
+
+    #[test]
+    fn edge_tiles_are_clipped() {
+        let mut tiles = TileQueue::new(100., 50., 32);
+        tiles.mark(&Rect::new(90., 40., 100., 100.));
+        assert_eq!(Some(Rect::new(64., 32., 32., 18.)), tiles.pop());
+        assert_eq!(Some(Rect::new(96., 32., 4., 18.)), tiles.pop());
+        assert!(tiles.is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+// a position in [0, 1] that plays forward over `duration` milliseconds of wall-clock time, and
+// can be paused or scrubbed to anywhere along it.
+pub struct Timeline {
+    duration: f64,
+    position: f64,
+    // when it was last advanced, while it's playing.
+    last_tick: Option<f64>,
+}
+
+impl Timeline {
+    // starts paused, at the end.
+    pub fn new(duration: f64) -> Self {
+        Self {
+            duration,
+            position: 1.,
+            last_tick: None,
+        }
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn set_duration(&mut self, duration: f64) {
+        self.duration = duration.max(1.);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.last_tick.is_some()
+    }
+
+    // plays from where it is, or from the start if it already reached the end.
+    pub fn play(&mut self, now: f64) {
+        if self.position >= 1. {
+            self.position = 0.;
+        }
+        self.last_tick = Some(now);
+    }
+
+    pub fn pause(&mut self) {
+        self.last_tick = None;
+    }
+
+    // jumps to `position`, carrying on playing from there if it was.
+    pub fn scrub(&mut self, position: f64) {
+        self.position = position.clamp(0., 1.);
+    }
+
+    // advances to `now`, returning the new position if it moved. it stops at the end.
+    pub fn tick(&mut self, now: f64) -> Option<f64> {
+        let last = self.last_tick?;
+        self.position = (self.position + (now - last) / self.duration).min(1.);
+        self.last_tick = if self.position < 1. { Some(now) } else { None };
+        Some(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timeline::*;
+
+    #[test]
+    fn plays_to_the_end_and_stops() {
+        let mut timeline = Timeline::new(1000.);
+        assert_eq!(None, timeline.tick(0.));
+
+        timeline.play(0.);
+        assert_eq!(Some(0.25), timeline.tick(250.));
+        assert_eq!(Some(1.), timeline.tick(2000.));
+        assert!(!timeline.is_playing());
+        assert_eq!(None, timeline.tick(3000.));
+    }
+
+    #[test]
+    fn pause_and_scrub() {
+        let mut timeline = Timeline::new(1000.);
+        timeline.play(0.);
+        timeline.tick(100.);
+        timeline.pause();
+        assert_eq!(None, timeline.tick(500.));
+        assert_eq!(0.1, timeline.position());
+
+        timeline.scrub(0.5);
+        timeline.play(600.);
+        assert_eq!(Some(0.6), timeline.tick(700.));
+        timeline.scrub(2.);
+        assert_eq!(1., timeline.position());
+    }
+}
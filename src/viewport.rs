@@ -0,0 +1,193 @@
+use crate::threed::Vec3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn empty() -> Self {
+        Self::new(0., 0., 0., 0.)
+    }
+
+    pub fn from_corners(x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        Self::new(x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs())
+    }
+
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0. || self.height <= 0.
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.right().min(other.right());
+        let y1 = self.bottom().min(other.bottom());
+        if x1 <= x0 || y1 <= y0 {
+            return Rect::empty();
+        }
+        Rect::from_corners(x0, y0, x1, y1)
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self.clone();
+        }
+        Rect::from_corners(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    pub fn expand(&self, margin: f64) -> Rect {
+        Rect::new(
+            self.x - margin,
+            self.y - margin,
+            self.width + 2. * margin,
+            self.height + 2. * margin,
+        )
+    }
+
+    // snaps the rect outward to whole pixels, so it can be iterated pixel-by-pixel.
+    pub fn to_pixels(&self) -> Rect {
+        if self.is_empty() {
+            return Rect::empty();
+        }
+        Rect::from_corners(
+            self.x.floor(),
+            self.y.floor(),
+            self.right().ceil(),
+            self.bottom().ceil(),
+        )
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        self.width as usize * self.height as usize
+    }
+}
+
+// the part of the canvas the renderer is responsible for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderRegion {
+    FullCanvas,
+    // the screen-space bounding box of the plant, as found by marching the flower sdf.
+    PlantBounds,
+    Crop(Rect),
+}
+
+// how the scene is zoomed and panned on the canvas: a scene point lands at `scale` times it plus
+// the offset. depth scales too, so distances scale the same in every direction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct View {
+    pub scale: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl View {
+    pub const MIN_SCALE: f64 = 0.1;
+    pub const MAX_SCALE: f64 = 10.;
+
+    pub fn identity() -> Self {
+        Self { scale: 1., x: 0., y: 0. }
+    }
+
+    pub fn to_screen(&self, pt: &Vec3) -> Vec3 {
+        Vec3::new(pt.x * self.scale + self.x, pt.y * self.scale + self.y, pt.z * self.scale)
+    }
+
+    pub fn to_scene(&self, pt: &Vec3) -> Vec3 {
+        Vec3::new((pt.x - self.x) / self.scale, (pt.y - self.y) / self.scale, pt.z / self.scale)
+    }
+
+    // zooms in by `factor` (out, below 1), keeping what's under the screen point x, y there.
+    pub fn zoom_about(&mut self, x: f64, y: f64, factor: f64) {
+        let scale = (self.scale * factor).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        let factor = scale / self.scale;
+        self.x = x - (x - self.x) * factor;
+        self.y = y - (y - self.y) * factor;
+        self.scale = scale;
+    }
+
+    // moves the scene by dx, dy screen pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::viewport::*;
+
+    #[test]
+    fn view_roundtrip() {
+        let mut view = View::identity();
+        view.zoom_about(100., 50., 2.);
+        view.pan(10., -5.);
+        let pt = Vec3::new(30., 40., 5.);
+        let screen = view.to_screen(&pt);
+        assert_eq!(Vec3::new(-30., 25., 10.), screen);
+        assert_eq!(pt, view.to_scene(&screen));
+    }
+
+    #[test]
+    fn zooms_about_a_point() {
+        let mut view = View::identity();
+        let under = view.to_scene(&Vec3::new(200., 300., 0.));
+        view.zoom_about(200., 300., 3.);
+        assert_eq!(Vec3::new(200., 300., 0.), view.to_screen(&under));
+        view.zoom_about(0., 0., 1000.);
+        assert_eq!(View::MAX_SCALE, view.scale);
+    }
+
+    #[test]
+    fn intersection() {
+        let a = Rect::new(0., 0., 10., 10.);
+        let b = Rect::new(5., 5., 10., 10.);
+        assert_eq!(Rect::new(5., 5., 5., 5.), a.intersection(&b));
+        assert!(a.intersection(&Rect::new(20., 20., 1., 1.)).is_empty());
+    }
+
+    #[test]
+    fn union() {
+        let a = Rect::new(0., 0., 10., 10.);
+        let b = Rect::new(5., 5., 10., 10.);
+        assert_eq!(Rect::new(0., 0., 15., 15.), a.union(&b));
+        assert_eq!(a, a.union(&Rect::empty()));
+    }
+
+    #[test]
+    fn to_pixels() {
+        let r = Rect::new(0.5, 1.25, 2., 2.);
+        assert_eq!(Rect::new(0., 1., 3., 3.), r.to_pixels());
+        assert_eq!(9, r.to_pixels().pixel_count());
+    }
+}
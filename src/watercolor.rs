@@ -0,0 +1,193 @@
+use crate::color::Color;
+use crate::noise::{Fbm, Noise};
+use crate::render::{Fragment, GBuffer, Sample};
+use crate::utils::{gaussian_blur, lerpf};
+
+// how far (in pixels) a pixel looks at its neighbors, for edge darkening and color bleeding.
+pub const RADIUS: i64 = 3;
+
+// pixels this much further away than their neighbor count as a different wash.
+const DEPTH_EDGE: f64 = 4.;
+
+// watercolor-style composition of one pixel. pigment is treated as a transmittance layered on
+// the paper (so it only ever darkens), with a density that:
+//  - pools along silhouettes and wherever one wash meets another (edge darkening),
+//  - clumps in the grain of the paper (granulation).
+// neighboring washes bleed into each other, and a little past the silhouette, and the paper
+// itself has a faint fibrous texture.
+pub fn stylize<P: Fn(&Fragment) -> Color>(
+    gbuffer: &GBuffer,
+    x: i64,
+    y: i64,
+    paper: &Color,
+    noise: &Noise,
+    pigment: &P) -> Color {
+    let (fx, fy) = (x as f64, y as f64);
+    let unit = |v: f64| 0.5 + 0.5 * v;
+    let paper = paper.clone().scale(0.94 + 0.06 * paper_texture(noise, fx, fy));
+
+    let center = match gbuffer.get(x, y) {
+        Sample::Surface(fragment) => Some(fragment),
+        _ => None,
+    };
+
+    let mut bleed = Color::black();
+    let mut bleed_weight = 0.;
+    let mut edges = 0.;
+    let mut neighbors = 0.;
+    for dy in -RADIUS..=RADIUS {
+        for dx in -RADIUS..=RADIUS {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let neighbor = match gbuffer.get(x + dx, y + dy) {
+                Sample::Surface(fragment) => Some(fragment),
+                _ => None,
+            };
+            if let Some(neighbor) = neighbor {
+                let w = gaussian_blur(RADIUS as f64 / 2., dx as f64, dy as f64);
+                bleed = bleed.add(w, &pigment(neighbor));
+                bleed_weight += w;
+            }
+            if let Some(center) = center {
+                neighbors += 1.;
+                if is_edge(center, neighbor) {
+                    edges += 1.;
+                }
+            }
+        }
+    }
+    let bleed = if bleed_weight > 0. { Some(bleed.scale(1. / bleed_weight)) } else { None };
+
+    let center = match center {
+        Some(center) => center,
+        None => {
+            // paint that wicked out past the silhouette.
+            return match bleed {
+                Some(bleed) => {
+                    let reach = 0.35 * bleed_weight.min(1.) * unit(noise.value2(fx / 6., fy / 6.));
+                    paper.clone().multiply(&Color::white().lerp(reach, &bleed))
+                }
+                None => paper,
+            };
+        }
+    };
+
+    let mut color = pigment(center);
+    if let Some(bleed) = bleed {
+        color = color.lerp(0.4 * unit(noise.simplex2(fx / 9. + 17., fy / 9.)), &bleed);
+    }
+
+    let edge = edges / neighbors;
+    let granulation = unit(noise.value2(fx / 1.7, fy / 1.7)) * 0.6
+        + (1. - noise.worley2(fx / 4., fy / 4.)).max(0.) * 0.4;
+    let density = (1. + 1.4 * edge) * lerpf(0.8, 1.25, granulation);
+
+    paper.multiply(&color.powf(density))
+}
+
+fn is_edge(center: &Fragment, neighbor: Option<&Fragment>) -> bool {
+    match neighbor {
+        None => true,
+        Some(neighbor) => {
+            neighbor.material.part != center.material.part
+                || (neighbor.point.z - center.point.z).abs() > DEPTH_EDGE
+        }
+    }
+}
+
+// faint laid-paper fibers: long streaks plus fine tooth, in [0, 1].
+fn paper_texture(noise: &Noise, x: f64, y: f64) -> f64 {
+    let (wx, wy) = noise.warp2(x / 40., y / 3., &Fbm::default(), 0.4);
+    let fibers = noise.perlin2(wx, wy);
+    let tooth = Fbm::default().sample2(|x, y| noise.value2(x, y), x / 2., y / 2.);
+    0.5 + 0.25 * fibers + 0.25 * tooth
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::flower::{Material, Part};
+    use crate::noise::Noise;
+    use crate::render::{Fragment, GBuffer, Sample};
+    use crate::threed::Vec3;
+    use crate::watercolor::*;
+
+    const SIZE: usize = 9;
+
+    fn surface(part: Part, z: f64) -> Sample {
+        Sample::Surface(Fragment {
+            point: Vec3::new(0., 0., z),
+            normal: Vec3::forward(),
+            material: Material { part, s: 0.5 },
+        })
+    }
+
+    // a gbuffer filled with one wash, with the right half replaced by `right`.
+    fn split(right: &Sample) -> GBuffer {
+        let mut gbuffer = GBuffer::new(SIZE, SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let sample = if x > SIZE / 2 { right.clone() } else { surface(Part::Stem, 0.) };
+                gbuffer.set(x, y, sample);
+            }
+        }
+        gbuffer
+    }
+
+    fn gray(_: &Fragment) -> Color {
+        Color::new(0.5, 0.5, 0.5)
+    }
+
+    fn brightness(color: &Color) -> f64 {
+        let (r, g, b) = color.to_srgb();
+        r + g + b
+    }
+
+    #[test]
+    fn edges_darken() {
+        let noise = Noise::new(1);
+        let paint = |gbuffer: &GBuffer| {
+            let center = (SIZE / 2) as i64;
+            stylize(gbuffer, center, center, &Color::white(), &noise, &gray)
+        };
+        let flat = paint(&split(&surface(Part::Stem, 0.)));
+        let depth_edge = paint(&split(&surface(Part::Stem, 50.)));
+        let part_edge = paint(&split(&surface(Part::Leaf, 0.)));
+        assert!(brightness(&depth_edge) < brightness(&flat));
+        assert!(brightness(&part_edge) < brightness(&flat));
+    }
+
+    #[test]
+    fn empty_is_paper() {
+        let gbuffer = GBuffer::new(SIZE, SIZE);
+        let noise = Noise::new(1);
+        let red = |_: &Fragment| Color::new(1., 0., 0.);
+        let paper = Color::from_srgb(0.9, 0.8, 0.7);
+        for y in 0..SIZE as i64 {
+            for x in 0..SIZE as i64 {
+                let painted = stylize(&gbuffer, x, y, &paper, &noise, &red);
+                assert_eq!(painted, stylize(&gbuffer, x, y, &paper, &noise, &gray));
+                // only the paper's own texture shades it.
+                let (r, g, b) = painted.to_srgb();
+                assert!(r > g && g > b);
+                assert!(brightness(&painted) <= brightness(&paper));
+            }
+        }
+    }
+
+    #[test]
+    fn deterministic_per_seed() {
+        let gbuffer = split(&surface(Part::Leaf, 20.));
+        let (a, b, c) = (Noise::new(4), Noise::new(4), Noise::new(5));
+        let mut differs = false;
+        for y in 0..SIZE as i64 {
+            for x in 0..SIZE as i64 {
+                let painted = stylize(&gbuffer, x, y, &Color::white(), &a, &gray);
+                assert_eq!(painted, stylize(&gbuffer, x, y, &Color::white(), &b, &gray));
+                differs |= painted != stylize(&gbuffer, x, y, &Color::white(), &c, &gray);
+            }
+        }
+        assert!(differs);
+    }
+}
@@ -10,22 +10,72 @@ const mainLoop = () => {
 
 setInterval(mainLoop, 15);
 
+// only keys bound to a command are kept from the browser.
 window.addEventListener('keydown', event => {
-  arose.handle_key_down(event.key);
-  event.preventDefault();
+  if (arose.handle_key_down(event.key, event.ctrlKey, event.altKey, event.shiftKey, event.metaKey)) {
+    event.preventDefault();
+  }
 });
 
-window.addEventListener("mousemove", event => {
-  arose.handle_mouse_move(event.offsetX, event.offsetY);
+const pointer = handler => event => {
+  handler.call(arose, event);
   event.preventDefault();
-});
+};
 
-window.addEventListener("mouseup", event => {
-  arose.handle_mouse_up(event.offsetX, event.offsetY);
-  event.preventDefault();
+window.addEventListener("pointermove", pointer(arose.handle_pointer_move));
+window.addEventListener("pointerup", pointer(arose.handle_pointer_up));
+window.addEventListener("pointerdown", pointer(arose.handle_pointer_down));
+
+window.addEventListener("pointercancel", event => {
+  arose.handle_pointer_cancel(event.pointerId);
 });
 
-window.addEventListener("mousedown", event => {
-  arose.handle_mouse_down(event.offsetX, event.offsetY);
+window.addEventListener("wheel", event => {
+  arose.handle_wheel(event.offsetX, event.offsetY, event.deltaY);
   event.preventDefault();
-});
+}, { passive: false });
+
+// the right mouse button pans, so it shouldn't bring up a menu.
+window.addEventListener("contextmenu", event => event.preventDefault());
+
+const download = (bytes, name) => {
+  const url = URL.createObjectURL(new Blob([bytes], { type: "image/png" }));
+  const link = document.createElement("a");
+  link.href = url;
+  link.download = name;
+  link.click();
+  // revoking straight away can cancel the download before the browser has started it.
+  setTimeout(() => URL.revokeObjectURL(url), 1000);
+};
+
+// checks the frame count before starting an export, since every frame is held in memory until
+// it's saved.
+const checkFrames = frames => {
+  const max = arose.max_export_frames();
+  if (!Number.isInteger(frames) || frames < 1 || frames > max) {
+    throw new RangeError(`an export needs between 1 and ${max} frames`);
+  }
+};
+
+window.exportGrowth = (frames, fps) => {
+  checkFrames(frames);
+  arose.export_growth(frames, fps);
+};
+
+window.exportTurntable = (frames, fps) => {
+  checkFrames(frames);
+  arose.export_turntable(frames, fps);
+};
+
+window.exportHandleKeyframes = (keyframes, frames, fps) => {
+  checkFrames(frames);
+  arose.export_handle_keyframes(new Float64Array(keyframes), frames, fps);
+};
+
+// saves the last export as numbered pngs, plus one animated png of all of them.
+window.saveExport = () => {
+  for (let i = 0; i < arose.export_frame_count(); i++) {
+    download(arose.export_frame_png(i), arose.export_frame_name(i));
+  }
+  download(arose.export_apng(), "animation.png");
+};